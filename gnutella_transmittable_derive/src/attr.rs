@@ -0,0 +1,325 @@
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Attribute, Error, Expr, ExprLit, Field, Ident, Lit, LitStr, Path, Token, Type, Variant,
+};
+
+/// A single `key`, `key = value` or `key(...)` entry inside `#[transmittable(...)]`.
+pub(crate) struct AttrArg {
+    pub(crate) key: Ident,
+    pub(crate) value: Option<Expr>,
+    /// Entries inside the parentheses of `key(...)`.
+    pub(crate) nested: Option<Vec<AttrArg>>,
+}
+
+impl Parse for AttrArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let mut value = None;
+        let mut nested = None;
+
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            value = Some(input.parse()?);
+        } else if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let args = Punctuated::<AttrArg, Token![,]>::parse_terminated(&content)?;
+            nested = Some(args.into_iter().collect());
+        }
+
+        Ok(AttrArg { key, value, nested })
+    }
+}
+
+impl AttrArg {
+    /// Returns true if this is a bare `name` entry.
+    pub(crate) fn is_bare(&self, name: &str) -> bool {
+        self.key == name && self.value.is_none() && self.nested.is_none()
+    }
+
+    /// Returns the value of a `key = value` entry, erroring out for a bare `key`.
+    pub(crate) fn value(&self) -> syn::Result<&Expr> {
+        self.value
+            .as_ref()
+            .ok_or_else(|| Error::new(self.key.span(), format!("expected `{} = ...`", self.key)))
+    }
+
+    /// Returns the value of a `key = "value"` entry.
+    pub(crate) fn str_value(&self) -> syn::Result<LitStr> {
+        match self.value()? {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit_str),
+                ..
+            }) => Ok(lit_str.clone()),
+            value => Err(Error::new_spanned(
+                value,
+                format!("expected `{} = \"...\"`", self.key),
+            )),
+        }
+    }
+
+    /// Returns the path of a `key = "path::to::fn"` entry.
+    pub(crate) fn path_value(&self) -> syn::Result<Path> {
+        self.str_value()?.parse()
+    }
+
+    /// Returns the expression of a `key = "expr"` or `key = expr` entry.
+    pub(crate) fn expr_value(&self) -> syn::Result<Expr> {
+        match self.value()? {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit_str),
+                ..
+            }) => lit_str.parse(),
+            value => Ok(value.clone()),
+        }
+    }
+}
+
+/// Collects the entries of all `#[transmittable(...)]` attributes in `attrs`.
+pub(crate) fn parse_args(attrs: &[Attribute]) -> syn::Result<Vec<AttrArg>> {
+    let mut args = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("transmittable") {
+            continue;
+        }
+
+        let parsed = attr.parse_args_with(Punctuated::<AttrArg, Token![,]>::parse_terminated)?;
+        args.extend(parsed);
+    }
+
+    Ok(args)
+}
+
+/// Returns the error for a `key` that is not understood at the place it was used.
+fn unknown_arg(arg: &AttrArg, place: &str) -> Error {
+    Error::new(
+        arg.key.span(),
+        format!("unknown transmittable {} attribute `{}`", place, arg.key),
+    )
+}
+
+/// Options that can be given to the struct or enum itself with `#[transmittable(...)]`.
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    /// Type of the tag preceding the fields of an enum variant.
+    pub(crate) tag: Option<Type>,
+    /// Function checking the whole value after deserializing and before serializing.
+    pub(crate) validate: Option<Path>,
+}
+
+impl ContainerAttrs {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+        let mut container_attrs = ContainerAttrs::default();
+
+        for arg in parse_args(attrs)? {
+            if arg.key == "tag" {
+                container_attrs.tag = Some(syn::parse2(arg.value()?.to_token_stream())?);
+            } else if arg.key == "validate" {
+                container_attrs.validate = Some(arg.path_value()?);
+            } else {
+                return Err(unknown_arg(&arg, "container"));
+            }
+        }
+
+        Ok(container_attrs)
+    }
+}
+
+/// Options that can be given to an enum variant with `#[transmittable(...)]`.
+#[derive(Default)]
+pub(crate) struct VariantAttrs {
+    /// Tag identifying the variant on the wire.
+    pub(crate) tag: Option<Expr>,
+}
+
+impl VariantAttrs {
+    pub(crate) fn from_variant(variant: &Variant) -> syn::Result<VariantAttrs> {
+        let mut variant_attrs = VariantAttrs::default();
+
+        for arg in parse_args(&variant.attrs)? {
+            if arg.key == "tag" {
+                variant_attrs.tag = Some(arg.value()?.clone());
+            } else {
+                return Err(unknown_arg(&arg, "variant"));
+            }
+        }
+
+        Ok(variant_attrs)
+    }
+}
+
+/// Byte order requested with `#[transmittable(endian = "...")]`.
+pub(crate) enum Endian {
+    Big,
+    Little,
+}
+
+/// Parses the value of an `endian = "..."` entry.
+fn parse_endian(arg: &AttrArg) -> syn::Result<Endian> {
+    let endian = arg.str_value()?;
+
+    match endian.value().as_str() {
+        "be" | "big" => Ok(Endian::Big),
+        "le" | "little" => Ok(Endian::Little),
+        _ => Err(Error::new(
+            endian.span(),
+            "expected `endian = \"be\"` or `endian = \"le\"`",
+        )),
+    }
+}
+
+/// Framing of a string or byte field requested with `#[transmittable(encoding = "...")]`.
+pub(crate) enum Encoding {
+    /// `"nul"`, NUL-terminated.
+    Nul,
+    /// `"len_u8"`, `"len_u16"` or `"len_u32"`, preceded by the length as the given type.
+    LenPrefixed(Ident),
+}
+
+/// Options that can be given to a field with `#[transmittable(...)]`.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub(crate) endian: Option<Endian>,
+    pub(crate) encoding: Option<Encoding>,
+    /// Maximum length of a field with an `encoding`.
+    pub(crate) max_len: Option<Expr>,
+    /// Name (or position) of the earlier field holding the number of items of a `Vec` field.
+    pub(crate) count: Option<LitStr>,
+    /// The field takes the rest of the input.
+    pub(crate) rest: bool,
+    /// The field is an `Option` that is `None` when the input is exhausted.
+    pub(crate) optional: bool,
+    /// The field is a socket address transmitted as the port followed by the IP.
+    pub(crate) port_first: bool,
+    /// Function checking the field after deserializing and before serializing.
+    pub(crate) validate: Option<Path>,
+    /// Range the field has to be in.
+    pub(crate) range: Option<Expr>,
+}
+
+impl FieldAttrs {
+    pub(crate) fn from_field(field: &Field) -> syn::Result<FieldAttrs> {
+        let mut field_attrs = FieldAttrs::default();
+
+        for arg in parse_args(&field.attrs)? {
+            if arg.key == "endian" {
+                field_attrs.endian = Some(parse_endian(&arg)?);
+            } else if arg.key == "encoding" {
+                let encoding = arg.str_value()?;
+                field_attrs.encoding = Some(match encoding.value().as_str() {
+                    "nul" => Encoding::Nul,
+                    "len_u8" => Encoding::LenPrefixed(Ident::new("u8", encoding.span())),
+                    "len_u16" => Encoding::LenPrefixed(Ident::new("u16", encoding.span())),
+                    "len_u32" => Encoding::LenPrefixed(Ident::new("u32", encoding.span())),
+                    _ => {
+                        return Err(Error::new(
+                            encoding.span(),
+                            "expected `encoding = \"nul\"`, `\"len_u8\"`, `\"len_u16\"` or `\"len_u32\"`",
+                        ))
+                    }
+                });
+            } else if arg.key == "max_len" {
+                field_attrs.max_len = Some(arg.value()?.clone());
+            } else if arg.key == "count" {
+                field_attrs.count = Some(arg.str_value()?);
+            } else if arg.is_bare("rest") {
+                field_attrs.rest = true;
+            } else if arg.is_bare("optional") {
+                field_attrs.optional = true;
+            } else if arg.is_bare("port_first") {
+                field_attrs.port_first = true;
+            } else if arg.key == "validate" {
+                field_attrs.validate = Some(arg.path_value()?);
+            } else if arg.key == "range" {
+                field_attrs.range = Some(arg.expr_value()?);
+            } else {
+                return Err(unknown_arg(&arg, "field"));
+            }
+        }
+
+        match field_attrs.encoding {
+            Some(Encoding::Nul) if field_attrs.endian.is_some() => {
+                return Err(Error::new(
+                    field.span(),
+                    "`endian` can't be used with `encoding = \"nul\"`",
+                ))
+            }
+            None if field_attrs.max_len.is_some() => {
+                return Err(Error::new(field.span(), "`max_len` requires `encoding`"))
+            }
+            _ => {}
+        }
+
+        if let Some(ref count) = field_attrs.count {
+            if field_attrs.endian.is_some() || field_attrs.encoding.is_some() {
+                return Err(Error::new(
+                    count.span(),
+                    "`count` can't be used with `endian` or `encoding`",
+                ));
+            }
+        }
+
+        if field_attrs.port_first
+            && (field_attrs.endian.is_some()
+                || field_attrs.encoding.is_some()
+                || field_attrs.count.is_some())
+        {
+            return Err(Error::new(
+                field.span(),
+                "`port_first` can't be used with `endian`, `encoding` or `count`",
+            ));
+        }
+
+        if field_attrs.rest || field_attrs.optional {
+            let other_attrs = field_attrs.endian.is_some()
+                || field_attrs.encoding.is_some()
+                || field_attrs.max_len.is_some()
+                || field_attrs.count.is_some()
+                || field_attrs.port_first;
+
+            if other_attrs || (field_attrs.rest && field_attrs.optional) {
+                return Err(Error::new(
+                    field.span(),
+                    "`rest` and `optional` can't be used with other transmittable attributes",
+                ));
+            }
+        }
+
+        Ok(field_attrs)
+    }
+}
+
+/// Options that can be given to a struct deriving `TransmittableFlags`
+/// with `#[transmittable(...)]`.
+#[derive(Default)]
+pub(crate) struct FlagsAttrs {
+    pub(crate) endian: Option<Endian>,
+    /// Names and values of the flags from `flags(NAME = value, ...)`.
+    pub(crate) flags: Vec<(Ident, Expr)>,
+}
+
+impl FlagsAttrs {
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<FlagsAttrs> {
+        let mut flags_attrs = FlagsAttrs::default();
+
+        for arg in parse_args(attrs)? {
+            if arg.key == "endian" {
+                flags_attrs.endian = Some(parse_endian(&arg)?);
+            } else if arg.key == "flags" && arg.nested.is_some() {
+                for flag in arg.nested.unwrap_or_default() {
+                    let value = flag.value()?.clone();
+                    flags_attrs.flags.push((flag.key, value));
+                }
+            } else {
+                return Err(unknown_arg(&arg, "flags"));
+            }
+        }
+
+        Ok(flags_attrs)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::attr::{Endian, FlagsAttrs};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{spanned::Spanned, Data, DeriveInput, Error, Fields};
+
+/// This function generates the code for `#[derive(TransmittableFlags)]`.
+///
+/// The struct has to be a newtype over an integer, which holds the bits
+/// as they are on the wire, including the ones without a named flag.
+pub(crate) fn expand_flags(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ref name = input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "#[derive(TransmittableFlags)] doesn't support generics",
+        ));
+    }
+
+    let fields = match input.data {
+        Data::Struct(ref data_struct) => &data_struct.fields,
+        _ => {
+            return Err(Error::new(
+                name.span(),
+                "#[derive(TransmittableFlags)] only works with struct",
+            ))
+        }
+    };
+
+    let bits_type = match fields {
+        Fields::Unnamed(ref fields_unnamed) if fields_unnamed.unnamed.len() == 1 => {
+            &fields_unnamed.unnamed[0].ty
+        }
+        _ => {
+            return Err(Error::new(
+                fields.span(),
+                "#[derive(TransmittableFlags)] requires a single unnamed field",
+            ))
+        }
+    };
+
+    let flags_attrs = FlagsAttrs::from_attrs(&input.attrs)?;
+
+    let flag_names: Vec<_> = flags_attrs.flags.iter().map(|(name, _)| name).collect();
+    let flag_values: Vec<_> = flags_attrs.flags.iter().map(|(_, value)| value).collect();
+    let flag_strs: Vec<_> = flag_names.iter().map(|name| name.to_string()).collect();
+
+    let codec = match flags_attrs.endian {
+        Some(Endian::Big) => quote!(gnutella::transmittable::BigEndian<#bits_type>),
+        Some(Endian::Little) | None => quote!(gnutella::transmittable::LittleEndian<#bits_type>),
+    };
+
+    Ok(quote! {
+        impl #name {
+            #(
+                pub const #flag_names: #name = #name(#flag_values);
+            )*
+
+            /// Returns the flags with no bits set.
+            pub const fn empty() -> #name {
+                #name(0)
+            }
+
+            /// Returns all the named flags.
+            pub const fn all() -> #name {
+                #name(0 #(| #flag_values)*)
+            }
+
+            /// Returns the flags with the exact `bits`, including unnamed ones.
+            pub const fn from_bits_retain(bits: #bits_type) -> #name {
+                #name(bits)
+            }
+
+            pub const fn bits(&self) -> #bits_type {
+                self.0
+            }
+
+            /// Returns the bits that are set but don't belong to any named flag.
+            pub const fn unknown_bits(&self) -> #bits_type {
+                self.0 & !#name::all().0
+            }
+
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns true if all the bits of `other` are set.
+            pub const fn contains(&self, other: #name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns true if any of the bits of `other` are set.
+            pub const fn intersects(&self, other: #name) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            pub fn insert(&mut self, other: #name) {
+                self.0 |= other.0;
+            }
+
+            pub fn remove(&mut self, other: #name) {
+                self.0 &= !other.0;
+            }
+
+            /// Inserts `other` if `value` is true and removes it otherwise.
+            pub fn set(&mut self, other: #name, value: bool) {
+                if value {
+                    self.insert(other);
+                } else {
+                    self.remove(other);
+                }
+            }
+        }
+
+        impl std::ops::BitOr for #name {
+            type Output = #name;
+
+            fn bitor(self, other: #name) -> #name {
+                #name(self.0 | other.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for #name {
+            fn bitor_assign(&mut self, other: #name) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl std::ops::BitAnd for #name {
+            type Output = #name;
+
+            fn bitand(self, other: #name) -> #name {
+                #name(self.0 & other.0)
+            }
+        }
+
+        impl std::ops::BitAndAssign for #name {
+            fn bitand_assign(&mut self, other: #name) {
+                self.0 &= other.0;
+            }
+        }
+
+        /// Lists the named flags that are set followed by the unknown bits, if any.
+        impl std::fmt::Debug for #name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let mut names: Vec<std::string::String> = Vec::new();
+                #(
+                    if #flag_values != 0 && self.contains(#name::#flag_names) {
+                        names.push(#flag_strs.to_string());
+                    }
+                )*
+                if self.unknown_bits() != 0 {
+                    names.push(format!("{:#x}", self.unknown_bits()));
+                }
+                write!(f, "{}({})", stringify!(#name), names.join(" | "))
+            }
+        }
+
+        impl gnutella::transmittable::Serializable for #name {
+            const FIXED_SIZE: std::option::Option<usize> =
+                <#codec as gnutella::transmittable::SerializableAs<#bits_type>>::FIXED_SIZE;
+
+            fn serialize_into<__W: std::io::Write + ?Sized>(&self, w: &mut __W)
+                -> std::result::Result<(), gnutella::transmittable::Error>
+            {
+                <#codec as gnutella::transmittable::SerializableAs<#bits_type>>
+                    ::serialize_as_into(&self.0, w)
+            }
+        }
+
+        impl<'de> gnutella::transmittable::Deserializable<'de> for #name {
+            fn deserialize(data: &'de [u8])
+                -> std::result::Result<(Self, usize), gnutella::transmittable::Error>
+            {
+                let (bits, bytes_parsed) =
+                    <#codec as gnutella::transmittable::DeserializableAs<'de, #bits_type>>
+                        ::deserialize_as(data)?;
+                Ok((#name(bits), bytes_parsed))
+            }
+        }
+
+        impl gnutella::transmittable::Transmittable for #name {}
+    })
+}
@@ -1,20 +1,25 @@
 extern crate proc_macro;
 
+mod attr;
+mod flags;
+
+use attr::{ContainerAttrs, Encoding, Endian, FieldAttrs, VariantAttrs};
 use proc_macro::TokenStream;
 use proc_macro2::{Span as Span2, TokenStream as TokenStream2};
 
 use syn::{
-    parse_macro_input, parse_quote, spanned::Spanned, Data, DeriveInput, Error, Field, Fields,
-    GenericParam, Generics, Ident, Index,
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DataEnum, DataStruct, DeriveInput,
+    Error, Expr, Field, Fields, GenericParam, Generics, Ident, Lifetime, LifetimeDef, Path,
+    TypeParamBound,
 };
 
-use quote::{format_ident, quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 
 macro_rules! derive_error {
     ($string: tt) => {
         Error::new(Span2::call_site(), $string)
             .to_compile_error()
-            .into();
+            .into()
     };
 }
 
@@ -26,60 +31,215 @@ macro_rules! derive_error {
 /// If the fields of struct don't implement the desired trait,
 /// an error will be raised when calling `<Type as Trait>::`.
 ///
-/// If the struct contains generic types, the trait is implemented
-/// such for a generic type `T`, `impl <T: Transmittable>`.
+/// If the struct contains generic types, the traits are implemented
+/// such for a generic type `T`, e.g., `impl <T: Serializable> Serializable`.
+///
+/// Structs can borrow from the input they are deserialized from by having
+/// fields like `&'a str` or `&'a [u8]`. For these, `Deserializable<'de>`
+/// is implemented with `'de: 'a` and `Transmittable` is not implemented.
+///
+/// Fields can be annotated with `#[transmittable(...)]` to change how
+/// they are put on the wire:
+///
+/// * `endian = "be"` / `endian = "le"` - Transmit the field in big/little endian
+///   byte order using [BigEndian](gnutella::transmittable::BigEndian) or
+///   [LittleEndian](gnutella::transmittable::LittleEndian).
+/// * `encoding = "nul"` - Transmit a string or byte field (`String`, `Vec<u8>`, `&str`, ...)
+///   NUL-terminated using [NulTerminated](gnutella::transmittable::NulTerminated).
+/// * `encoding = "len_u8"` / `"len_u16"` / `"len_u32"` - Transmit a string or byte field
+///   preceded by its length using [LenPrefixed](gnutella::transmittable::LenPrefixed).
+///   `endian` applies to the length.
+/// * `max_len = N` - Limit the length of a field with an `encoding` to `N` bytes.
+/// * `count = "field"` - Transmit a `Vec<T>` field as its items one after another,
+///   the number of which is given by the earlier integer field `field`. Serializing
+///   fails if `field` doesn't match the length of the `Vec`.
+/// * `port_first` - Transmit a `SocketAddrV4`/`SocketAddrV6` field as the port followed
+///   by the IP using [PortFirst](gnutella::transmittable::PortFirst), as in a Pong,
+///   instead of the IP followed by the port.
+/// * `rest` - Transmit a byte or string field (`Vec<u8>`, `Bytes`, `&[u8]`, ...) as
+///   the rest of the input using [Rest](gnutella::transmittable::Rest).
+///   It has to be the last field.
+/// * `optional` - Transmit an `Option<T>` field as nothing if `None` and as the value
+///   otherwise, using [Optional](gnutella::transmittable::Optional). It deserializes
+///   to `None` if the input is exhausted, so only `optional` fields can follow it.
+///
+/// Values can be checked after deserializing and before serializing with:
+///
+/// * `validate = "path::to::fn"` - On the struct/enum or on a field, calls
+///   `fn(&T) -> Result<(), E>` with the value, where `E: Display` is the reason
+///   for rejecting it.
+/// * `range = "1..=7"` - On a field, requires the value to be in the range.
+///
+/// Failures are reported as [InvalidValue](gnutella::transmittable::Error::InvalidValue)
+/// when deserializing and as
+/// [SerializationFailed](gnutella::transmittable::Error::SerializationFailed) when serializing.
+///
+/// Enums are supported as well. They need a `#[transmittable(tag = Type)]`
+/// attribute naming the type of the tag written before the fields of a variant,
+/// and each variant needs a `#[transmittable(tag = value)]` attribute (or an
+/// explicit discriminant) giving its tag:
+///
+/// ```ignore
+/// #[derive(Transmittable)]
+/// #[transmittable(tag = u8)]
+/// enum Payload {
+///     #[transmittable(tag = 0x00)]
+///     Ping,
+///     #[transmittable(tag = 0x80)]
+///     Query { min_speed: u16 },
+/// }
+/// ```
+///
+/// Deserializing an unknown tag results in an error.
 
-#[proc_macro_derive(Transmittable)]
+#[proc_macro_derive(Transmittable, attributes(transmittable))]
 pub fn derive_transmittable(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let ref name = input.ident;
 
-    let parse_struct_res = match parse_struct(&input) {
-        Ok(parse_struct_res) => parse_struct_res,
-        Err(e) => return e,
+    let parse_res = match input.data {
+        Data::Struct(ref data_struct) => parse_struct(&input, data_struct),
+        Data::Enum(ref data_enum) => parse_enum(&input, data_enum),
+        Data::Union(_) => {
+            return derive_error!("#[derive(Transmittable)] only works with struct and enum")
+        }
     };
 
-    // We take ref to the fields of struct because
-    // they need to be used inside quote! macro.
-    // Struct members can't be interpolated directly in quote!
-    let ref serialize_funcs = parse_struct_res.serialize_funcs;
-    let ref deserialize_funcs = parse_struct_res.deserialize_funcs;
-    let ref struct_maker = parse_struct_res.struct_maker;
+    let parse_res = match parse_res {
+        Ok(parse_res) => parse_res,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
 
-    let generics = add_trait_bound(input.generics, "Transmittable");
+    let container_attrs = match ContainerAttrs::from_attrs(&input.attrs) {
+        Ok(container_attrs) => container_attrs,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
 
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // The container's `validate` runs before any field is serialized
+    // and after all of them are deserialized.
+    let validate_serialize = gen_checks(
+        container_attrs.validate.as_ref(),
+        None,
+        quote!(self),
+        quote!(gnutella::transmittable::Error::serialization_failed(reason)),
+    );
+    let validate_deserialize = gen_checks(
+        container_attrs.validate.as_ref(),
+        None,
+        quote!(&value),
+        quote!(gnutella::transmittable::Error::invalid_value(reason)),
+    );
 
-    let expanded = quote! {
-        impl #impl_generics Serializable for #name #ty_generics #where_clause {
-            fn serialize_append(&self, mut v: std::vec::Vec<u8>)
-                -> std::result::Result<std::vec::Vec<u8>, std::boxed::Box<dyn std::error::Error>>
+    // We take ref to the fields of struct because
+    // they need to be used inside quote! macro.
+    // Struct members can't be interpolated directly in quote!
+    let ref serialize_funcs = parse_res.serialize_funcs;
+    let ref deserialize_funcs = parse_res.deserialize_funcs;
+    let ref struct_maker = parse_res.struct_maker;
+    let ref serialized_len_funcs = parse_res.serialized_len_funcs;
+    let ref fixed_size = parse_res.fixed_size;
+
+    // Generated code always refers to the lifetime of the input as `'de`.
+    let ref de: Lifetime = parse_quote!('de);
+
+    let serialize_generics = add_trait_bound(
+        input.generics.clone(),
+        parse_quote!(gnutella::transmittable::Serializable),
+    );
+    let deserialize_generics = add_trait_bound(
+        deserialize_generics(&input.generics, de),
+        parse_quote!(gnutella::transmittable::Deserializable<#de>),
+    );
+
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (ser_impl_generics, _, ser_where_clause) = serialize_generics.split_for_impl();
+    let (de_impl_generics, _, de_where_clause) = deserialize_generics.split_for_impl();
+
+    let mut expanded = quote! {
+        impl #ser_impl_generics gnutella::transmittable::Serializable
+            for #name #ty_generics #ser_where_clause
+        {
+            const FIXED_SIZE: std::option::Option<usize> = #fixed_size;
+
+            fn serialize_into<__W: std::io::Write + ?Sized>(&self, w: &mut __W)
+                -> std::result::Result<(), gnutella::transmittable::Error>
             {
+                #validate_serialize
                 #serialize_funcs
-                Ok(v)
+                Ok(())
+            }
+
+            fn serialized_len(&self) -> usize {
+                #serialized_len_funcs
             }
         }
 
-        impl #impl_generics Deserializable for #name #ty_generics #where_clause {
-            fn deserialize(data: &[u8])
-                -> std::result::Result<(Self, usize), std::boxed::Box<dyn std::error::Error>>
+        impl #de_impl_generics gnutella::transmittable::Deserializable<#de>
+            for #name #ty_generics #de_where_clause
+        {
+            fn deserialize(data: &#de [u8])
+                -> std::result::Result<(Self, usize), gnutella::transmittable::Error>
             {
                 let mut start: usize = 0;
                 #deserialize_funcs
-                Ok((#struct_maker, start))
+                let value = #struct_maker;
+                #validate_deserialize
+                Ok((value, start))
             }
         }
-
-        impl #impl_generics Transmittable for #name #ty_generics #where_clause {}
     };
 
+    // Types borrowing from the input can't be deserialized for every
+    // lifetime, so they only get `Serializable` and `Deserializable<'de>`.
+    if input.generics.lifetimes().next().is_none() {
+        let generics = add_trait_bound(
+            input.generics.clone(),
+            parse_quote!(gnutella::transmittable::Transmittable),
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+        expanded.extend(quote! {
+            impl #impl_generics gnutella::transmittable::Transmittable
+                for #name #ty_generics #where_clause {}
+        });
+    }
+
     TokenStream::from(expanded)
 }
 
-/// This is the struct returned from [parse_struct].
+/// This derive macro turns a newtype over an integer into a set of bit flags
+/// that is [Transmittable](gnutella::transmittable::Transmittable) as the integer.
+///
+/// The flags are named with `#[transmittable(flags(NAME = value, ...))]`, which
+/// become associated consts. Bits without a name are kept as they are, so a value
+/// with flags unknown to us round-trips losslessly. `#[transmittable(endian = "be")]`
+/// transmits the integer in big endian, as Gnutella does for the flags of a Query.
+///
+/// ```ignore
+/// #[derive(Clone, Copy, PartialEq, Eq, TransmittableFlags)]
+/// #[transmittable(endian = "be", flags(FIREWALLED = 0x4000, XML = 0x2000))]
+/// struct QueryFlags(u16);
+///
+/// assert_eq!(QueryFlags::FIREWALLED.serialize()?, [0x40, 0x00]);
+/// ```
+///
+/// Besides the consts, the struct gets `empty`, `all`, `from_bits_retain`, `bits`,
+/// `unknown_bits`, `is_empty`, `contains`, `intersects`, `insert`, `remove` and `set`,
+/// the `|` and `&` operators and a `Debug` impl listing the flags that are set.
+#[proc_macro_derive(TransmittableFlags, attributes(transmittable))]
+pub fn derive_transmittable_flags(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match flags::expand_flags(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
+
+/// This is the struct returned from [parse_struct], [parse_enum] and [parse_fields].
 struct ParseStructRes {
     /// `TokenStream2` containing code for all fields of struct
-    /// to serialize them and append to the existing vector `v`.
+    /// to serialize them and write them to `w`.
     serialize_funcs: TokenStream2,
 
     /// `TokenStream2` containing code for all fields of struct
@@ -90,6 +250,25 @@ struct ParseStructRes {
 
     /// `TokenStream2` containing code for contruct an instance of struct.
     struct_maker: TokenStream2,
+
+    /// `TokenStream2` containing the `+ serialized_len` terms of all fields,
+    /// which [parse_struct] and [parse_enum] turn into the body of `serialized_len`.
+    serialized_len_funcs: TokenStream2,
+
+    /// `TokenStream2` containing the `FIXED_SIZE` of all fields separated by commas,
+    /// which [parse_struct] and [parse_enum] turn into the `FIXED_SIZE` of the type.
+    fixed_size: TokenStream2,
+
+    /// Names (or positions for unnamed fields) of the fields handled so far,
+    /// which later fields can refer to, e.g., with `count`.
+    field_names: Vec<String>,
+
+    /// Whether a `rest` field has been handled, which has to be the last field.
+    has_rest: bool,
+
+    /// Bindings of the `optional` fields handled so far. Only more
+    /// `optional` fields can follow them.
+    optional_fields: Vec<Ident>,
 }
 
 impl ParseStructRes {
@@ -98,141 +277,620 @@ impl ParseStructRes {
             serialize_funcs: TokenStream2::new(),
             deserialize_funcs: TokenStream2::new(),
             struct_maker: TokenStream2::new(),
+            serialized_len_funcs: TokenStream2::new(),
+            fixed_size: TokenStream2::new(),
+            field_names: Vec::new(),
+            has_rest: false,
+            optional_fields: Vec::new(),
         }
     }
 }
 
-/// This function fills up an instance of [ParseStructRes]
-/// while iterating over all fields of the struct.
+/// This function generates the code for a struct.
 ///
-/// The struct can be named, unnamed or unit.
-/// In case of unit struct, basically nothing is done.
-/// For named and unnamed structs, [gen_code_for_fields] is called
-/// which fills up an instance of [ParseStructRes] over successive iterations.
-fn parse_struct(derive_input: &DeriveInput) -> Result<ParseStructRes, TokenStream> {
-    let mut parse_struct_res = ParseStructRes::new();
+/// `self` is destructured into the same bindings that [parse_fields]
+/// uses for deserialized values, so that the code generated for
+/// each field is the same for structs and enum variants.
+fn parse_struct(
+    derive_input: &DeriveInput,
+    data_struct: &DataStruct,
+) -> syn::Result<ParseStructRes> {
+    let ref name = derive_input.ident;
+
+    if let Some(tag) = ContainerAttrs::from_attrs(&derive_input.attrs)?.tag {
+        return Err(Error::new_spanned(
+            tag,
+            "`tag` can only be used with #[derive(Transmittable)] on enums",
+        ));
+    }
+
+    let mut parse_struct_res = parse_fields(&data_struct.fields, quote!(#name), "")?;
+
+    // The same pattern that constructs the struct destructures `self`.
+    let ref struct_maker = parse_struct_res.struct_maker;
+    let ref serialize_funcs = parse_struct_res.serialize_funcs;
+    let ref serialized_len_funcs = parse_struct_res.serialized_len_funcs;
+    let ref fixed_size = parse_struct_res.fixed_size;
+
+    parse_struct_res.serialize_funcs = quote! {
+        let #struct_maker = self;
+        #serialize_funcs
+    };
+
+    parse_struct_res.serialized_len_funcs = quote! {
+        let #struct_maker = self;
+        0 #serialized_len_funcs
+    };
 
-    let ref data = derive_input.data;
+    parse_struct_res.fixed_size = quote! {
+        gnutella::transmittable::fixed_size_sum(&[#fixed_size])
+    };
+
+    Ok(parse_struct_res)
+}
+
+/// This function generates the code for an enum.
+///
+/// Serialization writes the tag of the variant followed by its fields.
+/// Deserialization reads the tag and then deserializes the fields of the
+/// variant with the matching tag.
+fn parse_enum(derive_input: &DeriveInput, data_enum: &DataEnum) -> syn::Result<ParseStructRes> {
     let ref name = derive_input.ident;
 
-    match data {
-        Data::Struct(ref data_struct) => match data_struct.fields {
-            Fields::Named(ref fields_named) => {
-                for (field_no, field) in fields_named.named.iter().enumerate() {
-                    gen_code_for_fields(&mut parse_struct_res, field, field_no);
-                }
+    let tag_type = match ContainerAttrs::from_attrs(&derive_input.attrs)?.tag {
+        Some(tag_type) => tag_type,
+        None => {
+            return Err(Error::new(
+                Span2::call_site(),
+                "#[derive(Transmittable)] on enums requires #[transmittable(tag = Type)]",
+            ))
+        }
+    };
 
-                // parse_struct_res.struct_maker can't be interpolated directly in
-                // quote!(). So took a ref.
-                let ref struct_maker = parse_struct_res.struct_maker;
+    let mut serialize_arms = TokenStream2::new();
+    let mut deserialize_arms = TokenStream2::new();
+    let mut serialized_len_arms = TokenStream2::new();
+    let mut variant_fixed_sizes = TokenStream2::new();
+
+    for variant in data_enum.variants.iter() {
+        let ref variant_name = variant.ident;
+
+        let tag = match VariantAttrs::from_variant(variant)?.tag {
+            Some(tag) => tag,
+            None => match variant.discriminant {
+                Some((_, ref discriminant)) => discriminant.clone(),
+                None => return Err(Error::new(
+                    variant.span(),
+                    "variant requires #[transmittable(tag = value)] or an explicit discriminant",
+                )),
+            },
+        };
+
+        let ParseStructRes {
+            serialize_funcs,
+            deserialize_funcs,
+            struct_maker,
+            serialized_len_funcs,
+            fixed_size,
+            ..
+        } = parse_fields(
+            &variant.fields,
+            quote!(#name::#variant_name),
+            &format!("{}.", variant_name),
+        )?;
+
+        serialize_arms.extend(quote_spanned! {variant.span()=>
+            #struct_maker => {
+                let tag: #tag_type = #tag;
+                <#tag_type as gnutella::transmittable::Serializable>::serialize_into(&tag, w)
+                    .map_err(|e| e.in_field("tag"))?;
+                #serialize_funcs
+            }
+        });
 
-                parse_struct_res.struct_maker = quote_spanned! {fields_named.span()=>
-                    #name {
-                        #struct_maker
-                    }
-                };
+        deserialize_arms.extend(quote_spanned! {variant.span()=>
+            tag if tag == #tag => {
+                #deserialize_funcs
+                #struct_maker
             }
-            Fields::Unnamed(ref fields_unnamed) => {
-                for (field_no, field) in fields_unnamed.unnamed.iter().enumerate() {
-                    gen_code_for_fields(&mut parse_struct_res, field, field_no);
-                }
+        });
 
-                // parse_struct_res.struct_maker can't be interpolated directly in
-                // quote!(). So took a ref.
-                let ref struct_maker = parse_struct_res.struct_maker;
+        serialized_len_arms.extend(quote_spanned! {variant.span()=>
+            #struct_maker => {
+                let tag: #tag_type = #tag;
+                <#tag_type as gnutella::transmittable::Serializable>::serialized_len(&tag)
+                    #serialized_len_funcs
+            }
+        });
+
+        variant_fixed_sizes.extend(quote! {
+            gnutella::transmittable::fixed_size_sum(&[#fixed_size]),
+        });
+    }
 
-                parse_struct_res.struct_maker = quote_spanned! {fields_unnamed.span()=>
-                    #name(#struct_maker)
-                };
+    let mut parse_enum_res = ParseStructRes::new();
+
+    parse_enum_res.serialize_funcs = quote! {
+        match self {
+            #serialize_arms
+        }
+    };
+
+    parse_enum_res.deserialize_funcs = quote! {
+        let (tag, bytes_parsed) =
+            <#tag_type as gnutella::transmittable::Deserializable<'de>>::deserialize(&data[start..])
+                .map_err(|e| e.within("tag", start))?;
+        start += bytes_parsed;
+
+        let value = match tag {
+            #deserialize_arms
+            tag => {
+                return Err(gnutella::transmittable::Error::invalid_value(
+                    format!("unknown tag {:?} for {}", tag, stringify!(#name)),
+                ).within("tag", 0))
             }
-            Fields::Unit => {
-                parse_struct_res.struct_maker = quote! {
-                    #name
-                };
+        };
+    };
+
+    parse_enum_res.struct_maker = quote!(value);
+
+    parse_enum_res.serialized_len_funcs = quote! {
+        match self {
+            #serialized_len_arms
+        }
+    };
+
+    // An enum has a fixed size only if all its variants have the same fixed size.
+    parse_enum_res.fixed_size = quote! {
+        gnutella::transmittable::fixed_size_sum(&[
+            <#tag_type as gnutella::transmittable::Serializable>::FIXED_SIZE,
+            gnutella::transmittable::fixed_size_common(&[#variant_fixed_sizes]),
+        ])
+    };
+
+    Ok(parse_enum_res)
+}
+
+/// This function fills up an instance of [ParseStructRes]
+/// while iterating over `fields`, which belong to a struct
+/// or an enum variant with the path `name`.
+/// `path_prefix` is prepended to the names of the fields in errors.
+///
+/// The fields can be named, unnamed or unit.
+/// In case of unit fields, basically nothing is done.
+/// For named and unnamed fields, [gen_code_for_fields] is called
+/// which fills up an instance of [ParseStructRes] over successive iterations.
+///
+/// The resulting `struct_maker` doubles as a pattern binding
+/// every field to the variable the field is deserialized into.
+fn parse_fields(
+    fields: &Fields,
+    name: TokenStream2,
+    path_prefix: &str,
+) -> syn::Result<ParseStructRes> {
+    let mut parse_struct_res = ParseStructRes::new();
+
+    match fields {
+        Fields::Named(ref fields_named) => {
+            for (field_no, field) in fields_named.named.iter().enumerate() {
+                gen_code_for_fields(&mut parse_struct_res, field, field_no, path_prefix)?;
             }
-        },
-        _ => {
-            return Err(derive_error!(
-                "#[derive(Transmittable)] only works with struct"
-            ))
+
+            // parse_struct_res.struct_maker can't be interpolated directly in
+            // quote!(). So took a ref.
+            let ref struct_maker = parse_struct_res.struct_maker;
+
+            parse_struct_res.struct_maker = quote_spanned! {fields_named.span()=>
+                #name {
+                    #struct_maker
+                }
+            };
+        }
+        Fields::Unnamed(ref fields_unnamed) => {
+            for (field_no, field) in fields_unnamed.unnamed.iter().enumerate() {
+                gen_code_for_fields(&mut parse_struct_res, field, field_no, path_prefix)?;
+            }
+
+            // parse_struct_res.struct_maker can't be interpolated directly in
+            // quote!(). So took a ref.
+            let ref struct_maker = parse_struct_res.struct_maker;
+
+            parse_struct_res.struct_maker = quote_spanned! {fields_unnamed.span()=>
+                #name(#struct_maker)
+            };
+        }
+        Fields::Unit => {
+            parse_struct_res.struct_maker = quote! {
+                #name
+            };
         }
     };
 
     Ok(parse_struct_res)
 }
 
-/// This function is used by [parse_struct] to fill up an instance of [ParseStructRes]
+/// This function is used by [parse_fields] to fill up an instance of [ParseStructRes]
 /// upon successive iterations.
 ///
 /// * `parse_struct_res` - Partially updated [ParseStructRes] instance.
 /// * `field` - The field to work on.
 /// * `field_no` - Position of the field. (useful for tuple structs, e.g., self.0, self.1 etc.)
-fn gen_code_for_fields(parse_struct_res: &mut ParseStructRes, field: &Field, field_no: usize) {
-    let ref field_type = field.ty;
-    let ref field_name = field.ident;
+/// * `path_prefix` - Prepended to the field's name in errors.
+fn gen_code_for_fields(
+    parse_struct_res: &mut ParseStructRes,
+    field: &Field,
+    field_no: usize,
+    path_prefix: &str,
+) -> syn::Result<()> {
+    let field_attrs = FieldAttrs::from_field(field)?;
+    let ref field_ident = field_ident(field, field_no);
+    let ref field_path = field_path(field, field_no, path_prefix);
+
+    if let Some(ref count) = field_attrs.count {
+        if !parse_struct_res.field_names.contains(&count.value()) {
+            return Err(Error::new(
+                count.span(),
+                format!(
+                    "`count` must name an earlier field, not `{}`",
+                    count.value()
+                ),
+            ));
+        }
+    }
 
-    if let Some(field_name) = field_name {
-        // Serialize the current field and update the vector `v`
-        parse_struct_res
-            .serialize_funcs
-            .extend(quote_spanned! {field.span()=>
-                let v = <#field_type as Serializable>::serialize_append(&self.#field_name, v)?;
-            });
+    if parse_struct_res.has_rest {
+        return Err(Error::new(
+            field.span(),
+            "a `rest` field has to be the last field",
+        ));
+    }
 
-        // Deserialize bytes to generate an instance of current field's type
-        // and update `start` by incrementing `bytes_parsed`.
-        parse_struct_res
-            .deserialize_funcs
-            .extend(quote_spanned! {field.span()=>
-                let (#field_name, bytes_parsed)
-                    = <#field_type as Deserializable>::deserialize(&data[start..])?;
-                start += bytes_parsed;
-            });
+    if !parse_struct_res.optional_fields.is_empty() && !field_attrs.optional {
+        return Err(Error::new(
+            field.span(),
+            "only `optional` fields can follow an `optional` field",
+        ));
+    }
 
-        // This is used to make a new named struct instance.
-        parse_struct_res
-            .struct_maker
-            .extend(quote_spanned! {field.span()=>
-                #field_name,
-            });
-    } else {
-        let tuple_index = Index::from(field_no);
+    let serialize_call = gen_serialize_call(field, &field_attrs, quote!(#field_ident));
+    let deserialize_call = gen_deserialize_call(field, &field_attrs, quote!(&data[start..]));
+
+    // An optional field that is present can't follow one that is absent,
+    // as the absent one would take its bytes when deserializing.
+    if field_attrs.optional && !parse_struct_res.optional_fields.is_empty() {
+        let ref optional_fields = parse_struct_res.optional_fields;
 
-        // Serialize the current field and update the vector `v`
         parse_struct_res
             .serialize_funcs
             .extend(quote_spanned! {field.span()=>
-                let v = <#field_type as Serializable>::serialize_append(&self.#tuple_index, v)?;
+                if #field_ident.is_some() && (#(#optional_fields.is_none())||*) {
+                    return Err(gnutella::transmittable::Error::serialization_failed(
+                        "optional field is present after an absent one",
+                    )
+                    .in_field(#field_path));
+                }
             });
+    }
 
-        let deserialized_ident = format_ident!("deserialized_{}", field_no);
+    // Check the field before serializing it.
+    parse_struct_res.serialize_funcs.extend(gen_checks(
+        field_attrs.validate.as_ref(),
+        field_attrs.range.as_ref(),
+        quote!(#field_ident),
+        quote!(gnutella::transmittable::Error::serialization_failed(reason).in_field(#field_path)),
+    ));
+
+    // Serialize the current field and write it to `w`
+    parse_struct_res
+        .serialize_funcs
+        .extend(quote_spanned! {field.span()=>
+            #serialize_call.map_err(|e| e.in_field(#field_path))?;
+        });
+
+    let serialized_len_call = gen_serialized_len_call(field, &field_attrs, quote!(#field_ident));
+    let fixed_size = gen_fixed_size(field, &field_attrs);
+
+    parse_struct_res
+        .serialized_len_funcs
+        .extend(quote_spanned! {field.span()=>
+            + #serialized_len_call
+        });
+
+    parse_struct_res
+        .fixed_size
+        .extend(quote_spanned! {field.span()=>
+            #fixed_size,
+        });
+
+    // Deserialize bytes to generate an instance of current field's type
+    // and update `start` by incrementing `bytes_parsed`.
+    // Errors are tagged with the field and the offset it starts at,
+    // which accumulates into an absolute offset for nested structs.
+    // Checks of the field are reported at the offset it starts at.
+    let deserialize_checks = gen_checks(
+        field_attrs.validate.as_ref(),
+        field_attrs.range.as_ref(),
+        quote!(&#field_ident),
+        quote!(gnutella::transmittable::Error::invalid_value(reason).within(#field_path, start)),
+    );
+
+    parse_struct_res
+        .deserialize_funcs
+        .extend(quote_spanned! {field.span()=>
+            let (#field_ident, bytes_parsed) =
+                #deserialize_call.map_err(|e| e.within(#field_path, start))?;
+            #deserialize_checks
+            start += bytes_parsed;
+        });
+
+    // This is used to make a new struct instance.
+    let field_maker = match field.ident {
+        Some(ref field_name) => quote!(#field_name: #field_ident,),
+        None => quote!(#field_ident,),
+    };
 
-        // Deserialize bytes to generate an instance of current field's type
-        // and update `start` by incrementing `bytes_parsed`.
-        parse_struct_res
-            .deserialize_funcs
-            .extend(quote_spanned! {field.span()=>
-                let (#deserialized_ident, bytes_parsed)
-                    = <#field_type as Deserializable>::deserialize(&data[start..])?;
-                start += bytes_parsed;
-            });
+    parse_struct_res
+        .struct_maker
+        .extend(quote_spanned! {field.span()=> #field_maker});
 
-        // This is used to make a new tuple struct instance.
-        parse_struct_res
-            .struct_maker
-            .extend(quote_spanned! {field.span()=>
-                #deserialized_ident,
-            });
+    parse_struct_res.has_rest = field_attrs.rest;
+
+    if field_attrs.optional {
+        parse_struct_res.optional_fields.push(field_ident.clone());
+    }
+
+    parse_struct_res.field_names.push(match field.ident {
+        Some(ref field_name) => field_name.to_string(),
+        None => field_no.to_string(),
+    });
+
+    Ok(())
+}
+
+/// Generates statements returning an error if the reference `value` isn't accepted
+/// by the function `validate` or is not in `range`.
+///
+/// `make_error` is an expression building the error from the `String` `reason`.
+fn gen_checks(
+    validate: Option<&Path>,
+    range: Option<&Expr>,
+    value: TokenStream2,
+    make_error: TokenStream2,
+) -> TokenStream2 {
+    let mut checks = TokenStream2::new();
+
+    if let Some(validate) = validate {
+        checks.extend(quote_spanned! {validate.span()=>
+            if let Err(reason) = #validate(#value) {
+                let reason = reason.to_string();
+                return Err(#make_error);
+            }
+        });
+    }
+
+    if let Some(range) = range {
+        let range_str = range.to_token_stream().to_string();
+
+        checks.extend(quote_spanned! {range.span()=>
+            if !(#range).contains(#value) {
+                let reason = format!("{:?} is not in the range {}", #value, #range_str);
+                return Err(#make_error);
+            }
+        });
+    }
+
+    checks
+}
+
+/// Returns the name of the variable a field is bound to in the generated code,
+/// `field_<name>` for named fields and `field_<field_no>` for unnamed ones.
+///
+/// The prefix keeps fields from shadowing variables used by the generated code
+/// such as `w`, `data` and `start`.
+fn field_ident(field: &Field, field_no: usize) -> Ident {
+    match field.ident {
+        Some(ref field_name) => format_ident!("field_{}", field_name),
+        None => format_ident!("field_{}", field_no),
+    }
+}
+
+/// Returns the path of a field as reported by `gnutella::transmittable::Error`,
+/// i.e., its name (or position for unnamed fields) after `path_prefix`.
+fn field_path(field: &Field, field_no: usize, path_prefix: &str) -> String {
+    match field.ident {
+        Some(ref field_name) => format!("{}{}", path_prefix, field_name),
+        None => format!("{}{}", path_prefix, field_no),
+    }
+}
+
+/// Returns the wrapper type implementing `SerializableAs`/`DeserializableAs`
+/// for the field, if its attributes ask for a non-default encoding.
+fn field_codec(field: &Field, field_attrs: &FieldAttrs) -> Option<TokenStream2> {
+    let ref field_type = field.ty;
+
+    // `max_len` is passed on as the `MAX` of the framing type when given.
+    let max_len = field_attrs
+        .max_len
+        .as_ref()
+        .map(|max_len| quote!(, { #max_len }));
+
+    if field_attrs.rest {
+        return Some(quote!(gnutella::transmittable::Rest));
+    }
+
+    if field_attrs.optional {
+        return Some(quote!(gnutella::transmittable::Optional));
+    }
+
+    if field_attrs.port_first {
+        return Some(quote!(gnutella::transmittable::PortFirst<#field_type>));
+    }
+
+    match field_attrs.encoding {
+        Some(Encoding::Nul) => Some(quote! {
+            gnutella::transmittable::NulTerminated<#field_type #max_len>
+        }),
+        Some(Encoding::LenPrefixed(ref len_type)) => {
+            // With an encoding, `endian` applies to the length prefix.
+            let len_type = match field_attrs.endian {
+                Some(Endian::Big) => quote!(gnutella::transmittable::BigEndian<#len_type>),
+                Some(Endian::Little) | None => quote!(#len_type),
+            };
+
+            Some(quote! {
+                gnutella::transmittable::LenPrefixed<#len_type, #field_type #max_len>
+            })
+        }
+        None => field_attrs.endian.as_ref().map(|endian| match endian {
+            Endian::Big => quote!(gnutella::transmittable::BigEndian<#field_type>),
+            Endian::Little => quote!(gnutella::transmittable::LittleEndian<#field_type>),
+        }),
+    }
+}
+
+/// Generates an expression serializing the value behind the reference `value`
+/// and writing it to `w`.
+fn gen_serialize_call(
+    field: &Field,
+    field_attrs: &FieldAttrs,
+    value: TokenStream2,
+) -> TokenStream2 {
+    let ref field_type = field.ty;
+
+    // The count field is bound to a reference, like `value`.
+    if let Some(ref count) = field_attrs.count {
+        let count_ident = format_ident!("field_{}", count.value());
+        return quote_spanned! {field.span()=>
+            gnutella::transmittable::serialize_counted(
+                #value,
+                gnutella::transmittable::count_to_usize(*#count_ident),
+                w,
+            )
+        };
+    }
+
+    match field_codec(field, field_attrs) {
+        Some(codec) => quote_spanned! {field.span()=>
+            <#codec as gnutella::transmittable::SerializableAs<#field_type>>
+                ::serialize_as_into(#value, w)
+        },
+        None => quote_spanned! {field.span()=>
+            <#field_type as gnutella::transmittable::Serializable>::serialize_into(#value, w)
+        },
+    }
+}
+
+/// Generates an expression returning the serialized length
+/// of the value behind the reference `value`.
+fn gen_serialized_len_call(
+    field: &Field,
+    field_attrs: &FieldAttrs,
+    value: TokenStream2,
+) -> TokenStream2 {
+    let ref field_type = field.ty;
+
+    if field_attrs.count.is_some() {
+        return quote_spanned! {field.span()=>
+            gnutella::transmittable::serialized_len_counted(#value)
+        };
     }
+
+    match field_codec(field, field_attrs) {
+        Some(codec) => quote_spanned! {field.span()=>
+            <#codec as gnutella::transmittable::SerializableAs<#field_type>>
+                ::serialized_len_as(#value)
+        },
+        None => quote_spanned! {field.span()=>
+            <#field_type as gnutella::transmittable::Serializable>::serialized_len(#value)
+        },
+    }
+}
+
+/// Generates an expression evaluating to the `FIXED_SIZE` of the field.
+fn gen_fixed_size(field: &Field, field_attrs: &FieldAttrs) -> TokenStream2 {
+    let ref field_type = field.ty;
+
+    if field_attrs.count.is_some() {
+        return quote!(None);
+    }
+
+    match field_codec(field, field_attrs) {
+        Some(codec) => quote_spanned! {field.span()=>
+            <#codec as gnutella::transmittable::SerializableAs<#field_type>>::FIXED_SIZE
+        },
+        None => quote_spanned! {field.span()=>
+            <#field_type as gnutella::transmittable::Serializable>::FIXED_SIZE
+        },
+    }
+}
+
+/// Generates an expression deserializing the field from the byte slice `data`.
+fn gen_deserialize_call(
+    field: &Field,
+    field_attrs: &FieldAttrs,
+    data: TokenStream2,
+) -> TokenStream2 {
+    let ref field_type = field.ty;
+
+    // The count field has already been deserialized into a value.
+    if let Some(ref count) = field_attrs.count {
+        let count_ident = format_ident!("field_{}", count.value());
+        return quote_spanned! {field.span()=>
+            gnutella::transmittable::deserialize_counted(
+                #data,
+                gnutella::transmittable::count_to_usize(#count_ident),
+            )
+        };
+    }
+
+    match field_codec(field, field_attrs) {
+        Some(codec) => quote_spanned! {field.span()=>
+            <#codec as gnutella::transmittable::DeserializableAs<'de, #field_type>>::deserialize_as(#data)
+        },
+        None => quote_spanned! {field.span()=>
+            <#field_type as gnutella::transmittable::Deserializable<'de>>::deserialize(#data)
+        },
+    }
+}
+
+/// Returns `generics` with the lifetime `de` of the input data added,
+/// unless the type already has a lifetime with the same name.
+///
+/// `de` is required to outlive all the other lifetimes of the type,
+/// so that borrowed fields can be deserialized from the input.
+fn deserialize_generics(generics: &Generics, de: &Lifetime) -> Generics {
+    let mut generics = generics.clone();
+
+    let other_lifetimes: Vec<Lifetime> = generics
+        .lifetimes()
+        .map(|lifetime_def| lifetime_def.lifetime.clone())
+        .filter(|lifetime| lifetime != de)
+        .collect();
+
+    if generics
+        .lifetimes()
+        .all(|lifetime_def| lifetime_def.lifetime != *de)
+    {
+        generics
+            .params
+            .insert(0, GenericParam::Lifetime(LifetimeDef::new(de.clone())));
+    }
+
+    for lifetime_def in generics.lifetimes_mut() {
+        if lifetime_def.lifetime == *de {
+            lifetime_def.bounds.extend(other_lifetimes.iter().cloned());
+        }
+    }
+
+    generics
 }
 
 /// This function adds the a trait bound specified by `bound` to
 /// all the type params in `generics`.
-fn add_trait_bound(mut generics: Generics, bound: &str) -> Generics {
+fn add_trait_bound(mut generics: Generics, bound: TypeParamBound) -> Generics {
     for param in &mut generics.params {
         if let GenericParam::Type(ref mut type_param) = param {
-            let bound_ident = Ident::new(bound, type_param.span());
-            type_param.bounds.push(parse_quote!(#bound_ident));
+            type_param.bounds.push(bound.clone());
         }
     }
     generics
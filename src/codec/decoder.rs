@@ -0,0 +1,175 @@
+use super::Error;
+use crate::message::{Message, HEADER_LEN, MAX_PAYLOAD_LEN};
+use crate::transmittable::Deserializable;
+use std::convert::TryInto;
+
+/// Offset of the payload length in a header.
+const PAYLOAD_LEN_OFFSET: usize = 19;
+
+/// Turns bytes received in arbitrary chunks into [Message]s.
+///
+/// The payload length is read straight from the header, so descriptors
+/// of unknown types are framed and skipped like any other invalid one
+/// without losing track of where the next one starts.
+#[derive(Debug)]
+pub struct MessageDecoder {
+    buffer: Vec<u8>,
+    max_payload_len: u32,
+}
+
+impl Default for MessageDecoder {
+    fn default() -> MessageDecoder {
+        MessageDecoder::new()
+    }
+}
+
+impl MessageDecoder {
+    /// Returns a decoder accepting payloads of up to [MAX_PAYLOAD_LEN] bytes.
+    pub fn new() -> MessageDecoder {
+        MessageDecoder::with_max_payload_len(MAX_PAYLOAD_LEN)
+    }
+
+    /// Returns a decoder rejecting payloads over `max_payload_len` bytes.
+    ///
+    /// Headers don't allow more than [MAX_PAYLOAD_LEN] anyway, so a
+    /// bigger cap fails the same descriptors as invalid instead.
+    pub fn with_max_payload_len(max_payload_len: u32) -> MessageDecoder {
+        MessageDecoder {
+            buffer: Vec::new(),
+            max_payload_len,
+        }
+    }
+
+    /// Adds bytes received from the peer.
+    pub fn extend(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Number of bytes received but not yet decoded.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the next message, or `None` if it hasn't been fully received yet.
+    ///
+    /// A message that fails to deserialize is dropped and returned as
+    /// [Error::InvalidMessage], after which decoding can go on.
+    pub fn decode(&mut self) -> Result<Option<Message>, Error> {
+        if self.buffer.len() < HEADER_LEN {
+            return Ok(None);
+        }
+
+        let payload_len = u32::from_le_bytes(
+            self.buffer[PAYLOAD_LEN_OFFSET..HEADER_LEN]
+                .try_into()
+                .unwrap(),
+        );
+
+        if payload_len > self.max_payload_len {
+            return Err(Error::PayloadTooLarge {
+                len: payload_len,
+                max: self.max_payload_len,
+            });
+        }
+
+        let frame_len = HEADER_LEN + payload_len as usize;
+
+        if self.buffer.len() < frame_len {
+            return Ok(None);
+        }
+
+        let res = Message::deserialize(&self.buffer[..frame_len]);
+        self.buffer.drain(..frame_len);
+
+        match res {
+            Ok((message, _)) => Ok(Some(message)),
+            Err(source) => Err(Error::InvalidMessage { source }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageDecoder;
+    use crate::codec::Error;
+    use crate::message::{Message, Payload, Ping, HEADER_LEN};
+    use crate::transmittable::Serializable;
+
+    fn ping() -> Message {
+        Message::new(Payload::Ping(Ping {
+            extensions: vec![0xc3; 3],
+        }))
+    }
+
+    #[test]
+    fn test_decoder_byte_by_byte() {
+        let messages = [ping(), ping()];
+        let mut data = Vec::new();
+
+        for message in &messages {
+            if let Err(err) = message.serialize_into(&mut data) {
+                panic!("{}", err);
+            }
+        }
+
+        let mut decoder = MessageDecoder::new();
+        let mut decoded = Vec::new();
+
+        for &byte in &data {
+            decoder.extend(&[byte]);
+
+            match decoder.decode() {
+                Ok(Some(message)) => decoded.push(message),
+                Ok(None) => {}
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        assert_eq!(decoded, messages);
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_decoder_skips_invalid() {
+        let message = ping();
+
+        let mut data = match message.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        // An unknown payload type followed by a valid descriptor.
+        let mut unknown = data.clone();
+        unknown[16] = 0x31;
+        unknown.append(&mut data);
+
+        let mut decoder = MessageDecoder::new();
+        decoder.extend(&unknown);
+
+        match decoder.decode() {
+            Err(err @ Error::InvalidMessage { .. }) => assert!(err.is_recoverable()),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match decoder.decode() {
+            Ok(Some(decoded)) => assert_eq!(decoded, message),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_decoder_payload_cap() {
+        let data = match ping().serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        let mut decoder = MessageDecoder::with_max_payload_len(2);
+        decoder.extend(&data[..HEADER_LEN]);
+
+        match decoder.decode() {
+            Err(err @ Error::PayloadTooLarge { len: 3, max: 2 }) => assert!(!err.is_recoverable()),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
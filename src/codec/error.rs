@@ -0,0 +1,40 @@
+use crate::transmittable;
+use snafu::Snafu;
+use std::io;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// A header announced a payload bigger than the cap. The stream
+    /// can't be trusted after this, so the connection should be closed.
+    #[snafu(display("Payload of {} bytes is over the cap of {}", len, max))]
+    PayloadTooLarge { len: u32, max: u32 },
+    /// A descriptor was framed correctly but couldn't be deserialized.
+    /// It has been skipped and the next one can be read.
+    #[snafu(display("Invalid descriptor: {}", source))]
+    InvalidMessage { source: transmittable::Error },
+    /// Serializing a descriptor to queue it failed.
+    #[snafu(display("Failed to serialize descriptor: {}", source))]
+    Serialize { source: transmittable::Error },
+    /// The outgoing queue already holds as many descriptors as it can.
+    #[snafu(display("Outgoing queue is full with {} descriptors", capacity))]
+    QueueFull { capacity: usize },
+    /// The stream ended in the middle of a descriptor.
+    #[snafu(display("Stream ended with {} bytes of a partial descriptor", len))]
+    Truncated { len: usize },
+    #[snafu(display("Descriptor stream I/O failed: {}", source))]
+    Io { source: io::Error },
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Error {
+        Error::Io { source }
+    }
+}
+
+impl Error {
+    /// Returns true if the stream is still in sync after this error,
+    /// i.e., only the offending descriptor was lost.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, Error::InvalidMessage { .. } | Error::QueueFull { .. })
+    }
+}
@@ -0,0 +1,15 @@
+//! Framing of descriptors sent over a connection after the handshake.
+//!
+//! [MessageDecoder] turns received bytes into [Message](crate::message::Message)s
+//! without doing any I/O, [MessageReader] does the same over a blocking
+//! stream and [MessageWriter] sends them through a bounded queue.
+
+mod decoder;
+mod error;
+mod reader;
+mod writer;
+
+pub use decoder::MessageDecoder;
+pub use error::Error;
+pub use reader::MessageReader;
+pub use writer::{MessageWriter, DEFAULT_QUEUE_CAPACITY};
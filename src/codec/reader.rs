@@ -0,0 +1,99 @@
+use super::{Error, MessageDecoder};
+use crate::message::Message;
+use std::io::Read;
+
+/// Reads [Message]s from a blocking stream, e.g., a `TcpStream`.
+#[derive(Debug)]
+pub struct MessageReader<R> {
+    inner: R,
+    decoder: MessageDecoder,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(inner: R) -> MessageReader<R> {
+        MessageReader::with_decoder(inner, MessageDecoder::new())
+    }
+
+    /// Uses `decoder`, which may already hold bytes, like the ones read
+    /// past the end of the [handshake](crate::handshake::run).
+    pub fn with_decoder(inner: R, decoder: MessageDecoder) -> MessageReader<R> {
+        MessageReader { inner, decoder }
+    }
+
+    /// Reads the next message, or returns `None` if the stream
+    /// ended cleanly between two messages.
+    ///
+    /// See [MessageDecoder::decode] for errors that can be skipped.
+    pub fn read_message(&mut self) -> Result<Option<Message>, Error> {
+        let mut buf = [0; 4096];
+
+        loop {
+            if let Some(message) = self.decoder.decode()? {
+                return Ok(Some(message));
+            }
+
+            let bytes_read = self.inner.read(&mut buf)?;
+
+            if bytes_read == 0 {
+                return match self.decoder.buffered_len() {
+                    0 => Ok(None),
+                    len => Err(Error::Truncated { len }),
+                };
+            }
+
+            self.decoder.extend(&buf[..bytes_read]);
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageReader;
+    use crate::codec::{Error, MessageDecoder};
+    use crate::message::{Bye, Message, Payload};
+    use crate::transmittable::Serializable;
+
+    #[test]
+    fn test_reader_read_message() {
+        let message = Message::new(Payload::Bye(Bye {
+            code: 200,
+            message: "Shutting down".to_string(),
+        }));
+
+        let data = match message.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        // The first bytes were already read along with the handshake.
+        let mut decoder = MessageDecoder::new();
+        decoder.extend(&data[..5]);
+
+        let mut reader = MessageReader::with_decoder(&data[5..], decoder);
+
+        match reader.read_message() {
+            Ok(Some(read)) => assert_eq!(read, message),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match reader.read_message() {
+            Ok(None) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let mut reader = MessageReader::new(&data[..data.len() - 1]);
+
+        match reader.read_message() {
+            Err(Error::Truncated { len }) => assert_eq!(len, data.len() - 1),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::Error;
+use crate::message::Message;
+use crate::transmittable::Serializable;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+/// Number of descriptors queued per connection by default.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+/// Writes [Message]s to a stream through a bounded queue, so that
+/// a slow peer makes us drop descriptors rather than buffer them
+/// without limit.
+#[derive(Debug)]
+pub struct MessageWriter<W> {
+    inner: W,
+    queue: VecDeque<Vec<u8>>,
+    capacity: usize,
+    /// Bytes of the front of the queue already written.
+    written: usize,
+}
+
+impl<W: Write> MessageWriter<W> {
+    pub fn new(inner: W) -> MessageWriter<W> {
+        MessageWriter::with_capacity(inner, DEFAULT_QUEUE_CAPACITY)
+    }
+
+    /// Returns a writer queuing at most `capacity` descriptors.
+    pub fn with_capacity(inner: W, capacity: usize) -> MessageWriter<W> {
+        MessageWriter {
+            inner,
+            queue: VecDeque::new(),
+            capacity,
+            written: 0,
+        }
+    }
+
+    /// Serializes `message` and adds it to the queue.
+    ///
+    /// Fails with [Error::QueueFull] if the queue is full,
+    /// in which case `message` is dropped.
+    pub fn enqueue(&mut self, message: &Message) -> Result<(), Error> {
+        if self.queue.len() >= self.capacity {
+            return Err(Error::QueueFull {
+                capacity: self.capacity,
+            });
+        }
+
+        let data = message
+            .serialize()
+            .map_err(|source| Error::Serialize { source })?;
+        self.queue.push_back(data);
+
+        Ok(())
+    }
+
+    /// Writes out as much of the queue as the stream takes.
+    ///
+    /// A non-blocking stream returning `WouldBlock` leaves the
+    /// rest of the queue for the next call.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while let Some(data) = self.queue.front() {
+            match self.inner.write(&data[self.written..]) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+                Ok(len) => self.written += len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+
+            if self.written == data.len() {
+                self.queue.pop_front();
+                self.written = 0;
+            }
+        }
+
+        match self.inner.flush() {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            res => Ok(res?),
+        }
+    }
+
+    /// Queues `message` and flushes the queue.
+    pub fn send(&mut self, message: &Message) -> Result<(), Error> {
+        self.enqueue(message)?;
+        self.flush()
+    }
+
+    /// Number of descriptors waiting to be written, counting a partly written one.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageWriter;
+    use crate::codec::{Error, MessageReader};
+    use crate::message::{Message, Payload, Ping};
+    use std::io::{self, Write};
+
+    /// Takes at most 5 bytes per call and then blocks once.
+    struct SlowWriter {
+        data: Vec<u8>,
+        blocked: bool,
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.blocked = !self.blocked;
+
+            if self.blocked {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+
+            let len = buf.len().min(5);
+            self.data.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writer_bounded_queue() {
+        let messages = [
+            Message::new(Payload::Ping(Ping { extensions: vec![] })),
+            Message::new(Payload::Ping(Ping {
+                extensions: vec![1, 2, 3],
+            })),
+        ];
+
+        let mut writer = MessageWriter::with_capacity(
+            SlowWriter {
+                data: Vec::new(),
+                blocked: false,
+            },
+            2,
+        );
+
+        for message in &messages {
+            if let Err(err) = writer.enqueue(message) {
+                panic!("{}", err);
+            }
+        }
+
+        match writer.enqueue(&messages[0]) {
+            Err(err @ Error::QueueFull { capacity: 2 }) => assert!(err.is_recoverable()),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        while writer.queued() > 0 {
+            if let Err(err) = writer.flush() {
+                panic!("{}", err);
+            }
+        }
+
+        let data = writer.into_inner().data;
+        let mut reader = MessageReader::new(&data[..]);
+
+        for message in &messages {
+            match reader.read_message() {
+                Ok(Some(read)) => assert_eq!(&read, message),
+                res => panic!("unexpected result {:?}", res),
+            }
+        }
+    }
+}
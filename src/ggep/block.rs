@@ -0,0 +1,410 @@
+use super::cobs;
+use crate::transmittable::{ensure_len, Deserializable, Error, Serializable, Transmittable};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    str,
+};
+
+/// First byte of every GGEP block.
+pub const GGEP_MAGIC: u8 = 0xc3;
+
+/// Most data a compressed extension may expand to. Anything bigger is rejected
+/// rather than letting a few bytes on the wire make us allocate without bound.
+pub const MAX_DECOMPRESSED_LEN: usize = 64 * 1024;
+
+const FLAG_LAST: u8 = 0x80;
+const FLAG_COBS: u8 = 0x40;
+const FLAG_COMPRESSED: u8 = 0x20;
+const FLAG_RESERVED: u8 = 0x10;
+const ID_LEN_MASK: u8 = 0x0f;
+
+/// Largest data length the 3-byte length encoding can hold.
+const MAX_DATA_LEN: usize = (1 << 18) - 1;
+
+/// A single extension of a [GgepBlock].
+///
+/// `data` is kept decoded. `cobs` and `compressed` say how it
+/// is (or is to be) encoded on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GgepExtension {
+    /// 1 to 15 bytes, e.g., `"DU"` or `"IPP"`.
+    pub id: String,
+    pub data: Vec<u8>,
+    /// COBS encode the data, so that it contains no NUL bytes.
+    pub cobs: bool,
+    /// Compress the data with deflate (in a zlib stream, as servents do).
+    pub compressed: bool,
+}
+
+impl GgepExtension {
+    /// Returns an extension with `data` transmitted as is.
+    pub fn new(id: impl Into<String>, data: Vec<u8>) -> GgepExtension {
+        GgepExtension {
+            id: id.into(),
+            data,
+            cobs: false,
+            compressed: false,
+        }
+    }
+
+    /// Returns the data as it goes on the wire, compressed first and then COBS encoded.
+    fn encoded_data(&self) -> Result<Vec<u8>, Error> {
+        let mut data = self.data.clone();
+
+        if self.compressed {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&data)?;
+            data = encoder.finish()?;
+        }
+
+        if self.cobs {
+            data = cobs::encode(&data);
+        }
+
+        Ok(data)
+    }
+
+    /// Reverses [GgepExtension::encoded_data].
+    fn decode_data(flags: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut data = if flags & FLAG_COBS != 0 {
+            cobs::decode(data)?
+        } else {
+            data.to_vec()
+        };
+
+        if flags & FLAG_COMPRESSED != 0 {
+            let mut decompressed = Vec::new();
+            let limit = u64::try_from(MAX_DECOMPRESSED_LEN).unwrap_or(u64::MAX) + 1;
+
+            ZlibDecoder::new(&data[..])
+                .take(limit)
+                .read_to_end(&mut decompressed)
+                .map_err(|err| Error::invalid_value(format!("bad compressed data: {}", err)))?;
+
+            if decompressed.len() > MAX_DECOMPRESSED_LEN {
+                return Err(Error::invalid_value(format!(
+                    "compressed data expands to more than {} bytes",
+                    MAX_DECOMPRESSED_LEN
+                )));
+            }
+
+            data = decompressed;
+        }
+
+        Ok(data)
+    }
+}
+
+/// A GGEP (Gnutella Generic Extension Protocol) block, i.e., [GGEP_MAGIC]
+/// followed by one or more extensions, the last of which is flagged as such.
+///
+/// ```text
+/// +-------+-------+----+--------+------+-------+----+--------+------+
+/// | 0xC3  | Flags | ID | Length | Data | Flags | ID | Length | Data | ...
+/// +-------+-------+----+--------+------+-------+----+--------+------+
+/// ```
+///
+/// The length takes 1 to 3 bytes of 6 bits each, most significant first,
+/// with 0x80 set on all but the last byte and 0x40 set on the last one.
+///
+/// Common extensions have typed accessors, e.g., [GgepBlock::daily_uptime].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GgepBlock {
+    pub extensions: Vec<GgepExtension>,
+}
+
+impl GgepBlock {
+    pub fn new() -> GgepBlock {
+        GgepBlock::default()
+    }
+
+    /// Returns the extension with `id`, if present.
+    pub fn get(&self, id: &str) -> Option<&GgepExtension> {
+        self.extensions.iter().find(|extension| extension.id == id)
+    }
+
+    /// Adds `extension`, replacing any extension with the same ID.
+    pub fn insert(&mut self, extension: GgepExtension) {
+        match self.extensions.iter_mut().find(|e| e.id == extension.id) {
+            Some(existing) => *existing = extension,
+            None => self.extensions.push(extension),
+        }
+    }
+
+    /// Removes and returns the extension with `id`, if present.
+    pub fn remove(&mut self, id: &str) -> Option<GgepExtension> {
+        let index = self.extensions.iter().position(|e| e.id == id)?;
+        Some(self.extensions.remove(index))
+    }
+}
+
+/// Writes the GGEP encoding of `len`.
+fn serialize_data_len<W: Write + ?Sized>(len: usize, w: &mut W) -> Result<(), Error> {
+    if len > MAX_DATA_LEN {
+        return Err(Error::serialization_failed(format!(
+            "{} bytes of data is more than the maximum of {}",
+            len, MAX_DATA_LEN
+        )));
+    }
+
+    let bytes = [
+        0x80 | (len >> 12) as u8,
+        0x80 | (len >> 6 & 0x3f) as u8,
+        0x40 | (len & 0x3f) as u8,
+    ];
+
+    // Skip the leading bytes that only hold zeros.
+    let skip = if len < 1 << 6 {
+        2
+    } else if len < 1 << 12 {
+        1
+    } else {
+        0
+    };
+
+    w.write_all(&bytes[skip..])?;
+    Ok(())
+}
+
+/// Reads the GGEP encoding of a length, returning it along with the bytes it takes.
+fn deserialize_data_len(data: &[u8]) -> Result<(usize, usize), Error> {
+    let mut len = 0;
+
+    for index in 0..3 {
+        ensure_len(data, index + 1)?;
+        let byte = data[index];
+        len = len << 6 | usize::from(byte & 0x3f);
+
+        if byte & 0x40 != 0 {
+            return Ok((len, index + 1));
+        }
+
+        if byte & 0x80 == 0 {
+            return Err(
+                Error::invalid_value("length byte has neither 0x80 nor 0x40 set").at_offset(index),
+            );
+        }
+    }
+
+    Err(Error::invalid_value("length takes more than 3 bytes").at_offset(3))
+}
+
+impl Serializable for GgepBlock {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        if self.extensions.is_empty() {
+            return Err(Error::serialization_failed(
+                "GGEP block needs at least one extension",
+            ));
+        }
+
+        w.write_all(&[GGEP_MAGIC])?;
+
+        for (index, extension) in self.extensions.iter().enumerate() {
+            serialize_extension(extension, index + 1 == self.extensions.len(), w)
+                .map_err(|e| e.in_field(&index.to_string()).in_field("extensions"))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_extension<W: Write + ?Sized>(
+    extension: &GgepExtension,
+    last: bool,
+    w: &mut W,
+) -> Result<(), Error> {
+    let id = extension.id.as_bytes();
+
+    if id.is_empty() || id.len() > usize::from(ID_LEN_MASK) || id.contains(&0) {
+        return Err(
+            Error::serialization_failed("ID has to be 1 to 15 bytes without NUL").in_field("id"),
+        );
+    }
+
+    let mut flags = id.len() as u8;
+
+    if last {
+        flags |= FLAG_LAST;
+    }
+    if extension.cobs {
+        flags |= FLAG_COBS;
+    }
+    if extension.compressed {
+        flags |= FLAG_COMPRESSED;
+    }
+
+    let data = extension.encoded_data().map_err(|e| e.in_field("data"))?;
+
+    w.write_all(&[flags])?;
+    w.write_all(id)?;
+    serialize_data_len(data.len(), w).map_err(|e| e.in_field("data"))?;
+    w.write_all(&data)?;
+    Ok(())
+}
+
+impl<'de> Deserializable<'de> for GgepBlock {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        ensure_len(data, 1)?;
+
+        if data[0] != GGEP_MAGIC {
+            return Err(Error::invalid_value(format!(
+                "expected GGEP magic {:#x}, found {:#x}",
+                GGEP_MAGIC, data[0]
+            )));
+        }
+
+        let mut block = GgepBlock::new();
+        let mut start = 1;
+
+        loop {
+            let index = block.extensions.len();
+            let ((extension, last), bytes_parsed) = deserialize_extension(&data[start..])
+                .map_err(|e| e.within(&index.to_string(), 0).within("extensions", start))?;
+
+            block.extensions.push(extension);
+            start += bytes_parsed;
+
+            if last {
+                return Ok((block, start));
+            }
+        }
+    }
+}
+
+/// Reads an extension, returning it along with whether it is the last one.
+fn deserialize_extension(data: &[u8]) -> Result<((GgepExtension, bool), usize), Error> {
+    ensure_len(data, 1)?;
+    let flags = data[0];
+
+    if flags & FLAG_RESERVED != 0 {
+        return Err(Error::invalid_value("reserved flag is set"));
+    }
+
+    let id_len = usize::from(flags & ID_LEN_MASK);
+
+    if id_len == 0 {
+        return Err(Error::invalid_value("ID length is 0"));
+    }
+
+    let mut start = 1;
+
+    ensure_len(&data[start..], id_len).map_err(|e| e.within("id", start))?;
+    let id = str::from_utf8(&data[start..start + id_len])
+        .map_err(|err| Error::invalid_value(format!("ID is not valid UTF-8: {}", err)))
+        .map_err(|e| e.within("id", start))?;
+    start += id_len;
+
+    let (data_len, bytes_parsed) =
+        deserialize_data_len(&data[start..]).map_err(|e| e.within("data", start))?;
+    start += bytes_parsed;
+
+    ensure_len(&data[start..], data_len).map_err(|e| e.within("data", start))?;
+    let decoded = GgepExtension::decode_data(flags, &data[start..start + data_len])
+        .map_err(|e| e.within("data", start))?;
+
+    let extension = GgepExtension {
+        id: id.to_string(),
+        data: decoded,
+        cobs: flags & FLAG_COBS != 0,
+        compressed: flags & FLAG_COMPRESSED != 0,
+    };
+
+    Ok(((extension, flags & FLAG_LAST != 0), start + data_len))
+}
+
+impl Transmittable for GgepBlock {}
+
+#[cfg(test)]
+mod tests {
+    use super::{GgepBlock, GgepExtension, MAX_DECOMPRESSED_LEN};
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_ggep_block_transmittable() {
+        let mut block = GgepBlock::new();
+        block.insert(GgepExtension::new("DU", vec![0x10, 0x0e]));
+        block.insert(GgepExtension::new("GUE", vec![]));
+        block.insert(GgepExtension::new("XX", vec![0x20; 70]));
+
+        let block_serialized = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(
+            block_serialized[..13],
+            [0xc3, 0x02, b'D', b'U', 0x42, 0x10, 0x0e, 0x03, b'G', b'U', b'E', 0x40, 0x82]
+        );
+        // 70 bytes take two length bytes.
+        assert_eq!(block_serialized[15..17], [0x81, 0x46]);
+
+        match GgepBlock::deserialize(&block_serialized) {
+            Ok((block_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, block_serialized.len());
+                assert_eq!(block_deserialized, block);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_ggep_block_cobs_compressed() {
+        let mut block = GgepBlock::new();
+        block.insert(GgepExtension {
+            cobs: true,
+            compressed: true,
+            ..GgepExtension::new("PHC", vec![0; 1000])
+        });
+
+        let block_serialized = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert!(!block_serialized.contains(&0));
+        assert!(block_serialized.len() < 100);
+
+        match GgepBlock::deserialize(&block_serialized) {
+            Ok((block_deserialized, _)) => assert_eq!(block_deserialized, block),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_ggep_block_decompression_limit() {
+        let mut block = GgepBlock::new();
+        block.insert(GgepExtension {
+            compressed: true,
+            ..GgepExtension::new("XX", vec![0; MAX_DECOMPRESSED_LEN + 1])
+        });
+
+        let block_serialized = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        match GgepBlock::deserialize(&block_serialized) {
+            Err(err) => {
+                assert!(!err.is_eof());
+                assert_eq!(err.field().as_str(), "extensions.0.data");
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_ggep_block_truncated() {
+        match GgepBlock::deserialize(&[0xc3, 0x02, b'D', b'U', 0x42, 0x10]) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "extensions.0.data");
+                assert_eq!(err.offset(), Some(5));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert!(GgepBlock::deserialize(&[0x00]).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! Consistent Overhead Byte Stuffing, which GGEP uses to keep NUL bytes
+//! out of extension data where NUL would end a string, e.g., in QueryHit results.
+
+use crate::transmittable::Error;
+
+/// Returns `data` encoded such that it contains no NUL bytes.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() + data.len() / 254 + 1);
+    let mut code_index = 0;
+    let mut code = 1_u8;
+
+    encoded.push(0);
+
+    for &byte in data {
+        if byte != 0 {
+            encoded.push(byte);
+            code += 1;
+        }
+
+        if byte == 0 || code == 0xff {
+            encoded[code_index] = code;
+            code_index = encoded.len();
+            encoded.push(0);
+            code = 1;
+        }
+    }
+
+    encoded[code_index] = code;
+    encoded
+}
+
+/// Reverses [encode].
+pub fn decode(encoded: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::with_capacity(encoded.len());
+    let mut start = 0;
+
+    if let Some(nul) = encoded.iter().position(|&byte| byte == 0) {
+        return Err(Error::invalid_value("NUL byte in COBS encoded data").at_offset(nul));
+    }
+
+    while start < encoded.len() {
+        let code = usize::from(encoded[start]);
+
+        let block = match encoded.get(start + 1..start + code) {
+            Some(block) => block,
+            None => return Err(Error::unexpected_eof(start + code, encoded.len())),
+        };
+
+        data.extend_from_slice(block);
+        start += code;
+
+        // A maximal block isn't followed by a NUL, nor is the last block.
+        if code != 0xff && start < encoded.len() {
+            data.push(0);
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn test_cobs_round_trip() {
+        let cases: [(&[u8], &[u8]); 4] = [
+            (&[], &[0x01]),
+            (&[0x00], &[0x01, 0x01]),
+            (&[0x11, 0x22, 0x00, 0x33], &[0x03, 0x11, 0x22, 0x02, 0x33]),
+            (&[0x11, 0x00, 0x00], &[0x02, 0x11, 0x01, 0x01]),
+        ];
+
+        for &(data, encoded) in &cases {
+            assert_eq!(encode(data), encoded);
+
+            match decode(encoded) {
+                Ok(decoded) => assert_eq!(decoded, data),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        let long: Vec<u8> = (1..=255).chain(0..=255).map(|x| x as u8).collect();
+        let encoded = encode(&long);
+
+        assert!(!encoded.contains(&0));
+
+        match decode(&encoded) {
+            Ok(decoded) => assert_eq!(decoded, long),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_cobs_invalid() {
+        assert!(decode(&[0x02, 0x00]).is_err());
+
+        match decode(&[0x05, 0x11]) {
+            Err(err) => assert!(err.is_eof()),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Typed accessors for the GGEP extensions used by most servents.
+//!
+//! Getters return `Ok(None)` if the extension is absent and an error
+//! if it is present but malformed.
+
+use super::{GgepBlock, GgepExtension};
+use crate::transmittable::{deserialize_counted, DeserializableOwned, Error, Serializable};
+use crate::Transmittable;
+use std::{
+    net::{Ipv6Addr, SocketAddrV4},
+    str,
+};
+
+/// Contents of the `UP` extension of a Pong from an ultrapeer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Transmittable)]
+pub struct UltrapeerInfo {
+    /// Version of the ultrapeer protocol, 0x01 for 0.1.
+    pub version: u8,
+    pub free_leaf_slots: u8,
+    pub free_ultrapeer_slots: u8,
+}
+
+/// Contents of the `H` extension, the hash of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Transmittable)]
+#[transmittable(tag = u8)]
+pub enum GgepHash {
+    #[transmittable(tag = 0x01)]
+    Sha1([u8; 20]),
+    /// SHA-1 followed by the Tiger tree root.
+    #[transmittable(tag = 0x02)]
+    Bitprint([u8; 20], [u8; 24]),
+}
+
+/// Deserializes all of the data of `extension` as a `T`.
+fn parse_exact<T: DeserializableOwned>(extension: &GgepExtension) -> Result<T, Error> {
+    let (value, bytes_parsed) = T::deserialize(&extension.data)?;
+
+    if bytes_parsed != extension.data.len() {
+        return Err(Error::invalid_value(format!(
+            "{} bytes left over after the value",
+            extension.data.len() - bytes_parsed
+        ))
+        .at_offset(bytes_parsed));
+    }
+
+    Ok(value)
+}
+
+impl GgepBlock {
+    /// Returns the data of the extension `id` parsed as a `T`.
+    fn get_as<T: DeserializableOwned>(&self, id: &str) -> Result<Option<T>, Error> {
+        self.get(id)
+            .map(|extension| parse_exact(extension).map_err(|e| e.in_field(id)))
+            .transpose()
+    }
+
+    /// Inserts the extension `id` with `value` serialized as its data.
+    fn insert_as<T: Serializable>(&mut self, id: &str, value: &T) -> Result<(), Error> {
+        let data = value.serialize().map_err(|e| e.in_field(id))?;
+        self.insert(GgepExtension::new(id, data));
+        Ok(())
+    }
+
+    /// `DU`, the average number of seconds per day the servent is up.
+    ///
+    /// Transmitted as a little endian integer of 1 to 4 bytes.
+    pub fn daily_uptime(&self) -> Result<Option<u32>, Error> {
+        let extension = match self.get("DU") {
+            Some(extension) => extension,
+            None => return Ok(None),
+        };
+
+        if extension.data.is_empty() || extension.data.len() > 4 {
+            return Err(Error::invalid_value(format!(
+                "expected 1 to 4 bytes, found {}",
+                extension.data.len()
+            ))
+            .in_field("DU"));
+        }
+
+        let mut bytes = [0_u8; 4];
+        bytes[..extension.data.len()].copy_from_slice(&extension.data);
+        Ok(Some(u32::from_le_bytes(bytes)))
+    }
+
+    pub fn set_daily_uptime(&mut self, seconds: u32) {
+        let bytes = seconds.to_le_bytes();
+        let len = bytes
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(1, |i| i + 1);
+        self.insert(GgepExtension::new("DU", bytes[..len].to_vec()));
+    }
+
+    /// `UP`, sent by ultrapeers in Pongs.
+    pub fn ultrapeer(&self) -> Result<Option<UltrapeerInfo>, Error> {
+        self.get_as("UP")
+    }
+
+    pub fn set_ultrapeer(&mut self, info: UltrapeerInfo) -> Result<(), Error> {
+        self.insert_as("UP", &info)
+    }
+
+    /// `IP` in a Pong, the address of the receiver of the Ping as seen by the sender.
+    ///
+    /// In a Ping, `IP` has no data and asks for it, see [GgepBlock::get].
+    pub fn ip(&self) -> Result<Option<SocketAddrV4>, Error> {
+        self.get_as("IP")
+    }
+
+    pub fn set_ip(&mut self, addr: SocketAddrV4) -> Result<(), Error> {
+        self.insert_as("IP", &addr)
+    }
+
+    /// `IPP`, addresses of other ultrapeers packed one after another.
+    pub fn ipp(&self) -> Result<Option<Vec<SocketAddrV4>>, Error> {
+        let extension = match self.get("IPP") {
+            Some(extension) => extension,
+            None => return Ok(None),
+        };
+
+        let addr_len = SocketAddrV4::FIXED_SIZE.unwrap_or(6);
+
+        if extension.data.len() % addr_len != 0 {
+            return Err(Error::invalid_value(format!(
+                "{} bytes is not a multiple of {}",
+                extension.data.len(),
+                addr_len
+            ))
+            .in_field("IPP"));
+        }
+
+        let (addrs, _) =
+            deserialize_counted(&extension.data, Some(extension.data.len() / addr_len))
+                .map_err(|e| e.in_field("IPP"))?;
+        Ok(Some(addrs))
+    }
+
+    pub fn set_ipp(&mut self, addrs: &[SocketAddrV4]) -> Result<(), Error> {
+        let mut data = Vec::new();
+
+        for addr in addrs {
+            addr.serialize_into(&mut data)
+                .map_err(|e| e.in_field("IPP"))?;
+        }
+
+        self.insert(GgepExtension::new("IPP", data));
+        Ok(())
+    }
+
+    /// `LOC`, the locale of the servent's user, e.g., `en` or `en_US`.
+    pub fn locale(&self) -> Result<Option<String>, Error> {
+        self.get("LOC")
+            .map(|extension| {
+                str::from_utf8(&extension.data)
+                    .map(String::from)
+                    .map_err(|err| {
+                        Error::invalid_value(format!("locale is not valid UTF-8: {}", err))
+                            .in_field("LOC")
+                    })
+            })
+            .transpose()
+    }
+
+    pub fn set_locale(&mut self, locale: &str) {
+        self.insert(GgepExtension::new("LOC", locale.as_bytes().to_vec()));
+    }
+
+    /// `H`, the hash of a file in a QueryHit result or a Query.
+    pub fn hash(&self) -> Result<Option<GgepHash>, Error> {
+        self.get_as("H")
+    }
+
+    pub fn set_hash(&mut self, hash: GgepHash) -> Result<(), Error> {
+        self.insert_as("H", &hash)
+    }
+
+    /// `NP` in a Query, asking ultrapeers not to proxy out-of-band replies.
+    pub fn no_proxy(&self) -> bool {
+        self.get("NP").is_some()
+    }
+
+    pub fn set_no_proxy(&mut self, no_proxy: bool) {
+        self.set_presence("NP", no_proxy);
+    }
+
+    /// `SO`, support for secure out-of-band replies.
+    pub fn secure_oob(&self) -> bool {
+        self.get("SO").is_some()
+    }
+
+    pub fn set_secure_oob(&mut self, secure_oob: bool) {
+        self.set_presence("SO", secure_oob);
+    }
+
+    /// Inserts the extension `id` without data if `present`, removes it otherwise.
+    fn set_presence(&mut self, id: &str, present: bool) {
+        if present {
+            self.insert(GgepExtension::new(id, Vec::new()));
+        } else {
+            self.remove(id);
+        }
+    }
+
+    /// `PHC` in a Pong, packed GWebCache and UDP host cache entries,
+    /// one per line. Usually compressed.
+    pub fn packed_host_caches(&self) -> Result<Option<Vec<String>>, Error> {
+        self.get("PHC")
+            .map(|extension| {
+                let text = str::from_utf8(&extension.data).map_err(|err| {
+                    Error::invalid_value(format!("host caches are not valid UTF-8: {}", err))
+                        .in_field("PHC")
+                })?;
+
+                Ok(text
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect())
+            })
+            .transpose()
+    }
+
+    pub fn set_packed_host_caches(&mut self, host_caches: &[String]) {
+        self.insert(GgepExtension {
+            compressed: true,
+            ..GgepExtension::new("PHC", host_caches.join("\n").into_bytes())
+        });
+    }
+
+    /// `6`, the IPv6 address of the servent.
+    pub fn ipv6(&self) -> Result<Option<Ipv6Addr>, Error> {
+        self.get_as("6")
+    }
+
+    pub fn set_ipv6(&mut self, addr: Ipv6Addr) -> Result<(), Error> {
+        self.insert_as("6", &addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GgepHash, UltrapeerInfo};
+    use crate::ggep::{GgepBlock, GgepExtension};
+    use crate::transmittable::{Deserializable, Serializable};
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4};
+
+    fn round_trip(block: &GgepBlock) -> GgepBlock {
+        let block_serialized = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        match GgepBlock::deserialize(&block_serialized) {
+            Ok((block, _)) => block,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_ggep_typed_extensions() -> Result<(), crate::transmittable::Error> {
+        let addr = SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 6346);
+        let info = UltrapeerInfo {
+            version: 1,
+            free_leaf_slots: 10,
+            free_ultrapeer_slots: 2,
+        };
+        let hash = GgepHash::Sha1([7; 20]);
+
+        let mut block = GgepBlock::new();
+        block.set_daily_uptime(3600);
+        block.set_ultrapeer(info)?;
+        block.set_ip(addr)?;
+        block.set_ipp(&[addr, addr])?;
+        block.set_locale("en");
+        block.set_hash(hash)?;
+        block.set_no_proxy(true);
+        block.set_secure_oob(false);
+        block.set_packed_host_caches(&["gwc.example.com:80".to_string()]);
+        block.set_ipv6(Ipv6Addr::LOCALHOST)?;
+
+        assert_eq!(block.get("DU").map(|e| e.data.len()), Some(2));
+
+        let block = round_trip(&block);
+
+        assert_eq!(block.daily_uptime()?, Some(3600));
+        assert_eq!(block.ultrapeer()?, Some(info));
+        assert_eq!(block.ip()?, Some(addr));
+        assert_eq!(block.ipp()?, Some(vec![addr, addr]));
+        assert_eq!(block.locale()?, Some("en".to_string()));
+        assert_eq!(block.hash()?, Some(hash));
+        assert!(block.no_proxy());
+        assert!(!block.secure_oob());
+        assert_eq!(
+            block.packed_host_caches()?,
+            Some(vec!["gwc.example.com:80".to_string()])
+        );
+        assert_eq!(block.ipv6()?, Some(Ipv6Addr::LOCALHOST));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ggep_malformed_extension() {
+        let mut block = GgepBlock::new();
+        block.insert(GgepExtension::new("IPP", vec![1, 2, 3, 4, 5]));
+        block.insert(GgepExtension::new("UP", vec![1, 2, 3, 4]));
+
+        match block.ipp() {
+            Err(err) => assert_eq!(err.field().as_str(), "IPP"),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match block.ultrapeer() {
+            Err(err) => {
+                assert_eq!(err.field().as_str(), "UP");
+                assert_eq!(err.offset(), Some(3));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert_eq!(block.daily_uptime().ok(), Some(None));
+    }
+}
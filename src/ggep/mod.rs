@@ -0,0 +1,9 @@
+//! GGEP, the Gnutella Generic Extension Protocol, which carries
+//! extensions in Pongs, Queries, QueryHits and Pushes.
+
+mod block;
+pub mod cobs;
+mod extensions;
+
+pub use block::{GgepBlock, GgepExtension, GGEP_MAGIC, MAX_DECOMPRESSED_LEN};
+pub use extensions::{GgepHash, UltrapeerInfo};
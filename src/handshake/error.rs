@@ -0,0 +1,40 @@
+use snafu::Snafu;
+use std::io;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The bytes received don't form a handshake message.
+    #[snafu(display("Malformed handshake message: {}", reason))]
+    Malformed { reason: String },
+    /// The headers didn't end within [MAX_HANDSHAKE_LEN](super::MAX_HANDSHAKE_LEN) bytes.
+    #[snafu(display("Handshake message is longer than {} bytes", max))]
+    TooLong { max: usize },
+    /// The message is valid but not the one expected at this step.
+    #[snafu(display("Unexpected handshake message: {}", reason))]
+    Unexpected { reason: String },
+    /// The connection was closed before the handshake completed.
+    #[snafu(display("Connection closed during the handshake"))]
+    Closed,
+    #[snafu(display("Handshake I/O failed: {}", source))]
+    Io { source: io::Error },
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Error {
+        Error::Io { source }
+    }
+}
+
+impl Error {
+    pub fn malformed(reason: impl Into<String>) -> Error {
+        Error::Malformed {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn unexpected(reason: impl Into<String>) -> Error {
+        Error::Unexpected {
+            reason: reason.into(),
+        }
+    }
+}
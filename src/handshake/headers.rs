@@ -0,0 +1,201 @@
+use super::Error;
+use std::fmt;
+
+/// Headers of a handshake message, looked up ignoring case.
+///
+/// Keeps the order and the case of the names as received, so that
+/// they are sent back the way they were set.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Headers {
+    entries: Vec<(String, String)>,
+}
+
+impl Headers {
+    pub fn new() -> Headers {
+        Headers::default()
+    }
+
+    /// Returns the value of the header `name`, if present.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets the header `name` to `value`, replacing any previous value.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+        {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// Adds `value` to the header `name`, joining it to any previous
+    /// value with a comma as a repeated header would be.
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+        {
+            Some(entry) => {
+                entry.1.push_str(", ");
+                entry.1.push_str(&value);
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// Removes and returns the value of the header `name`, if present.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Returns true if the comma separated values of the header
+    /// `name` include `token`, ignoring case.
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get(name).into_iter().any(|value| {
+            value
+                .split(',')
+                .any(|item| item.trim().eq_ignore_ascii_case(token))
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Parses header lines, where a line starting with a space or a tab
+    /// continues the value of the previous one.
+    pub(crate) fn parse_lines<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Headers, Error> {
+        let mut headers = Headers::new();
+        // Entry the previous line went to, which isn't the last one if
+        // it repeated an earlier header.
+        let mut previous: Option<usize> = None;
+
+        for line in lines {
+            if line.starts_with(' ') || line.starts_with('\t') {
+                let (_, value) = previous
+                    .and_then(|index| headers.entries.get_mut(index))
+                    .ok_or_else(|| Error::malformed("continuation line without a header"))?;
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            }
+
+            let (name, value) = line.split_once(':').ok_or_else(|| {
+                Error::malformed(format!("header line without a colon: {:?}", line))
+            })?;
+            let name = name.trim();
+
+            if name.is_empty() {
+                return Err(Error::malformed("header with an empty name"));
+            }
+
+            headers.append(name, value.trim());
+            previous = headers
+                .entries
+                .iter()
+                .position(|(key, _)| key.eq_ignore_ascii_case(name));
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Writes each header as a `Name: value` line ending with CRLF.
+impl fmt::Display for Headers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.entries {
+            write!(f, "{}: {}\r\n", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Headers;
+
+    #[test]
+    fn test_headers_parse_lines() {
+        let lines = [
+            "User-Agent: Test/1.0",
+            "x-ultrapeer:True",
+            "Accept-Encoding: deflate",
+            "accept-encoding: gzip",
+            "X-Long: first",
+            "\tsecond",
+        ];
+
+        let headers = match Headers::parse_lines(lines.iter().copied()) {
+            Ok(headers) => headers,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers.get("user-agent"), Some("Test/1.0"));
+        assert_eq!(headers.get("X-Ultrapeer"), Some("True"));
+        assert_eq!(headers.get("Accept-Encoding"), Some("deflate, gzip"));
+        assert!(headers.has_token("ACCEPT-ENCODING", "GZIP"));
+        assert_eq!(headers.get("x-long"), Some("first second"));
+
+        // A folded line continues the header it follows even when that
+        // one was merged into an earlier entry.
+        let lines = ["X-Try: a", "Foo: b", "X-Try: c", " d"];
+
+        let headers = match Headers::parse_lines(lines.iter().copied()) {
+            Ok(headers) => headers,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(headers.get("X-Try"), Some("a, c d"));
+        assert_eq!(headers.get("Foo"), Some("b"));
+
+        assert!(Headers::parse_lines([" orphan"].iter().copied()).is_err());
+        assert!(Headers::parse_lines(["no colon"].iter().copied()).is_err());
+    }
+
+    #[test]
+    fn test_headers_insert_remove() {
+        let mut headers = Headers::new();
+        headers.insert("X-Try", "1.2.3.4:6346");
+        headers.insert("x-try", "5.6.7.8:6346");
+
+        assert_eq!(headers.to_string(), "X-Try: 5.6.7.8:6346\r\n");
+        assert_eq!(headers.remove("X-TRY"), Some("5.6.7.8:6346".to_string()));
+        assert!(headers.is_empty());
+    }
+}
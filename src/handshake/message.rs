@@ -0,0 +1,206 @@
+use super::{Error, Headers, MAX_HANDSHAKE_LEN};
+use std::fmt;
+
+/// Status line code and reason phrase of a handshake response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub code: u16,
+    pub reason: String,
+}
+
+impl Status {
+    pub const OK: u16 = 200;
+    pub const SERVICE_UNAVAILABLE: u16 = 503;
+
+    pub fn new(code: u16, reason: impl Into<String>) -> Status {
+        Status {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    /// `200 OK`, accepting the connection.
+    pub fn ok() -> Status {
+        Status::new(Status::OK, "OK")
+    }
+
+    /// `503 Service Unavailable`, usually sent when all the slots are taken.
+    pub fn busy() -> Status {
+        Status::new(Status::SERVICE_UNAVAILABLE, "Service Unavailable")
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.code == Status::OK
+    }
+}
+
+/// First line of a handshake message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartLine {
+    /// `GNUTELLA CONNECT/<version>`, sent by the servent opening the connection.
+    Connect { version: String },
+    /// `GNUTELLA/<version> <code> <reason>`, sent in response.
+    Response { version: String, status: Status },
+}
+
+impl StartLine {
+    fn parse(line: &str) -> Result<StartLine, Error> {
+        if let Some(version) = line.strip_prefix("GNUTELLA CONNECT/") {
+            return Ok(StartLine::Connect {
+                version: version.trim().to_string(),
+            });
+        }
+
+        let response = line
+            .strip_prefix("GNUTELLA/")
+            .ok_or_else(|| Error::malformed(format!("invalid start line: {:?}", line)))?;
+
+        let (version, status) = response.split_once(' ').unwrap_or((response, ""));
+        let (code, reason) = status.split_once(' ').unwrap_or((status, ""));
+
+        let code = code
+            .parse()
+            .map_err(|_| Error::malformed(format!("invalid status code: {:?}", code)))?;
+
+        Ok(StartLine::Response {
+            version: version.to_string(),
+            status: Status::new(code, reason.trim()),
+        })
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            StartLine::Connect { version } | StartLine::Response { version, .. } => version,
+        }
+    }
+}
+
+impl fmt::Display for StartLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartLine::Connect { version } => write!(f, "GNUTELLA CONNECT/{}", version),
+            StartLine::Response { version, status } => {
+                write!(f, "GNUTELLA/{} {} {}", version, status.code, status.reason)
+            }
+        }
+    }
+}
+
+/// A handshake message, i.e., a start line followed by headers
+/// and an empty line, each ending with CRLF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeMessage {
+    pub start_line: StartLine,
+    pub headers: Headers,
+}
+
+impl HandshakeMessage {
+    /// Parses the message at the start of `data`, returning it along with
+    /// the bytes it takes, or `None` if the empty line ending it hasn't
+    /// been received yet. Lines may also end with a bare LF.
+    pub fn parse(data: &[u8]) -> Result<Option<(HandshakeMessage, usize)>, Error> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let end = match data[start..].iter().position(|&byte| byte == b'\n') {
+                Some(len) => start + len,
+                None if data.len() > MAX_HANDSHAKE_LEN => {
+                    return Err(Error::TooLong {
+                        max: MAX_HANDSHAKE_LEN,
+                    })
+                }
+                None => return Ok(None),
+            };
+
+            let line = &data[start..end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            start = end + 1;
+
+            if start > MAX_HANDSHAKE_LEN {
+                return Err(Error::TooLong {
+                    max: MAX_HANDSHAKE_LEN,
+                });
+            }
+
+            if line.is_empty() {
+                break;
+            }
+
+            lines.push(String::from_utf8_lossy(line).into_owned());
+        }
+
+        let (start_line, header_lines) = lines
+            .split_first()
+            .ok_or_else(|| Error::malformed("empty start line"))?;
+
+        let message = HandshakeMessage {
+            start_line: StartLine::parse(start_line)?,
+            headers: Headers::parse_lines(header_lines.iter().map(String::as_str))?,
+        };
+
+        Ok(Some((message, start)))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("{}\r\n{}\r\n", self.start_line, self.headers).into_bytes()
+    }
+
+    /// Returns the status if this is a response.
+    pub fn status(&self) -> Option<&Status> {
+        match self.start_line {
+            StartLine::Response { ref status, .. } => Some(status),
+            StartLine::Connect { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HandshakeMessage, StartLine, Status};
+    use crate::handshake::{Error, MAX_HANDSHAKE_LEN};
+
+    #[test]
+    fn test_handshake_message_parse() {
+        let data = b"GNUTELLA/0.6 503 Service Unavailable\r\nX-Try: 1.2.3.4:6346\r\n\r\n\x00\x01";
+
+        match HandshakeMessage::parse(data) {
+            Ok(Some((message, bytes_parsed))) => {
+                assert_eq!(bytes_parsed, data.len() - 2);
+                assert_eq!(message.start_line.version(), "0.6");
+                assert_eq!(message.status(), Some(&Status::busy()));
+                assert_eq!(message.headers.get("x-try"), Some("1.2.3.4:6346"));
+                assert_eq!(message.to_bytes(), data[..bytes_parsed]);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match HandshakeMessage::parse(b"GNUTELLA CONNECT/0.6\nUser-Agent: x\n\n") {
+            Ok(Some((message, _))) => assert_eq!(
+                message.start_line,
+                StartLine::Connect {
+                    version: "0.6".to_string()
+                }
+            ),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_handshake_message_incomplete() {
+        match HandshakeMessage::parse(b"GNUTELLA CONNECT/0.6\r\nUser-Agent: x\r\n") {
+            Ok(None) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match HandshakeMessage::parse(&vec![b'a'; MAX_HANDSHAKE_LEN + 1]) {
+            Err(Error::TooLong { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match HandshakeMessage::parse(b"HTTP/1.1 200 OK\r\n\r\n") {
+            Err(Error::Malformed { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! The Gnutella 0.6 handshake exchanging HTTP-like headers before
+//! descriptors are sent over a connection.
+//!
+//! [Handshake] only deals with bytes, so it can be driven by anything;
+//! [run] drives it over a blocking stream.
+
+mod error;
+mod headers;
+mod message;
+mod state;
+mod stream;
+
+pub use error::Error;
+pub use headers::Headers;
+pub use message::{HandshakeMessage, StartLine, Status};
+pub use state::{Handshake, HandshakeConfig, Negotiated, Outcome, PROTOCOL_VERSION};
+pub use stream::run;
+
+/// Maximum length of a handshake message, to not buffer forever
+/// for a peer that never ends its headers.
+pub const MAX_HANDSHAKE_LEN: usize = 16 * 1024;
@@ -0,0 +1,544 @@
+use super::{Error, HandshakeMessage, Headers, StartLine, Status};
+use std::{
+    mem,
+    net::{IpAddr, SocketAddr},
+};
+
+/// Version of the protocol sent in the start lines.
+pub const PROTOCOL_VERSION: &str = "0.6";
+
+/// Our side of the handshake.
+#[derive(Debug, Clone)]
+pub struct HandshakeConfig {
+    pub user_agent: String,
+    /// Sent as `X-Ultrapeer`.
+    pub ultrapeer: bool,
+    /// Version of the query routing protocol to offer as `X-Query-Routing`.
+    pub query_routing: Option<String>,
+    /// Whether to offer and accept deflate compressed descriptors. Off by
+    /// default, as the [codec](crate::codec) only handles uncompressed streams.
+    pub deflate: bool,
+    /// Address we accept connections on, sent as `Listen-IP`.
+    pub listen_addr: Option<SocketAddr>,
+    /// IP of the peer as we see it, sent as `Remote-IP`.
+    pub remote_ip: Option<IpAddr>,
+    /// Servents to suggest when rejecting a connection, sent as `X-Try`.
+    pub x_try: Vec<SocketAddr>,
+}
+
+impl Default for HandshakeConfig {
+    fn default() -> HandshakeConfig {
+        HandshakeConfig {
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string(),
+            ultrapeer: false,
+            query_routing: Some("0.1".to_string()),
+            deflate: false,
+            listen_addr: None,
+            remote_ip: None,
+            x_try: Vec::new(),
+        }
+    }
+}
+
+/// What both sides agreed on, along with what the peer told about itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated {
+    /// All the headers sent by the peer.
+    pub headers: Headers,
+    pub user_agent: Option<String>,
+    /// `X-Ultrapeer` of the peer, if it sent one.
+    pub peer_ultrapeer: Option<bool>,
+    /// Query routing version both sides support, if any.
+    pub query_routing: Option<String>,
+    /// Whether the peer compresses what it sends with deflate.
+    pub deflate_incoming: bool,
+    /// Whether we have to compress what we send with deflate.
+    pub deflate_outgoing: bool,
+    /// `Listen-IP` of the peer, where it accepts connections.
+    pub peer_listen_addr: Option<SocketAddr>,
+    /// `Remote-IP` sent by the peer, i.e., our IP as it sees it.
+    pub our_ip: Option<IpAddr>,
+}
+
+/// How the handshake ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Connected(Negotiated),
+    /// One of the sides refused the connection with `status`,
+    /// possibly suggesting other servents to try.
+    Rejected {
+        status: Status,
+        x_try: Vec<SocketAddr>,
+    },
+}
+
+enum State {
+    /// Initiator waiting for the response to its `GNUTELLA CONNECT`.
+    AwaitingResponse,
+    /// Acceptor waiting for the `GNUTELLA CONNECT`.
+    AwaitingConnect,
+    /// Acceptor waiting for [Handshake::accept] or [Handshake::reject].
+    AwaitingDecision(HandshakeMessage),
+    /// Acceptor waiting for the initiator to confirm its response.
+    AwaitingFinal(Negotiated),
+    Done(Outcome),
+}
+
+/// The three-step Gnutella 0.6 handshake, without any I/O.
+///
+/// ```text
+/// Initiator                          Acceptor
+///     GNUTELLA CONNECT/0.6 + headers ->
+///     <- GNUTELLA/0.6 200 OK + headers
+///     GNUTELLA/0.6 200 OK + headers  ->
+/// ```
+///
+/// Bytes received from the peer are fed with [Handshake::receive] and
+/// bytes to send to it are taken with [Handshake::take_output]. Once
+/// [Handshake::outcome] is set, the bytes received after the last
+/// message are the first descriptors, returned by [Handshake::into_outcome].
+pub struct Handshake {
+    config: HandshakeConfig,
+    state: State,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+/// Returns true if `version` is `major.minor` with at least 0.6.
+fn is_supported_version(version: &str) -> bool {
+    let mut parts = version.splitn(2, '.').map(str::parse::<u32>);
+
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => (major, minor) >= (0, 6),
+        _ => false,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Returns the valid addresses of the comma separated `X-Try` header.
+fn parse_x_try(headers: &Headers) -> Vec<SocketAddr> {
+    headers.get("X-Try").map_or_else(Vec::new, |value| {
+        value
+            .split(',')
+            .filter_map(|addr| addr.trim().parse().ok())
+            .collect()
+    })
+}
+
+fn response(status: Status, headers: Headers) -> HandshakeMessage {
+    HandshakeMessage {
+        start_line: StartLine::Response {
+            version: PROTOCOL_VERSION.to_string(),
+            status,
+        },
+        headers,
+    }
+}
+
+impl Handshake {
+    /// Starts the handshake on the side opening the connection,
+    /// with the `GNUTELLA CONNECT` ready to be sent.
+    pub fn initiate(config: HandshakeConfig) -> Handshake {
+        let connect = HandshakeMessage {
+            start_line: StartLine::Connect {
+                version: PROTOCOL_VERSION.to_string(),
+            },
+            headers: Handshake::our_headers(&config),
+        };
+
+        Handshake {
+            config,
+            state: State::AwaitingResponse,
+            input: Vec::new(),
+            output: connect.to_bytes(),
+        }
+    }
+
+    /// Starts the handshake on the side accepting the connection.
+    pub fn respond(config: HandshakeConfig) -> Handshake {
+        Handshake {
+            config,
+            state: State::AwaitingConnect,
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Headers describing ourselves, sent in the first message of each side.
+    fn our_headers(config: &HandshakeConfig) -> Headers {
+        let mut headers = Headers::new();
+        headers.insert("User-Agent", config.user_agent.as_str());
+        headers.insert(
+            "X-Ultrapeer",
+            if config.ultrapeer { "True" } else { "False" },
+        );
+
+        if let Some(ref version) = config.query_routing {
+            headers.insert("X-Query-Routing", version.as_str());
+        }
+
+        if config.deflate {
+            headers.insert("Accept-Encoding", "deflate");
+        }
+
+        if let Some(addr) = config.listen_addr {
+            headers.insert("Listen-IP", addr.to_string());
+        }
+
+        if let Some(ip) = config.remote_ip {
+            headers.insert("Remote-IP", ip.to_string());
+        }
+
+        headers
+    }
+
+    /// Works out what both sides agreed on from the headers sent by the peer.
+    fn negotiate(&self, headers: &Headers) -> Negotiated {
+        let query_routing = match (&self.config.query_routing, headers.get("X-Query-Routing")) {
+            (Some(ours), Some(theirs)) if ours.as_str() == theirs.trim() => Some(ours.clone()),
+            _ => None,
+        };
+
+        Negotiated {
+            headers: headers.clone(),
+            user_agent: headers.get("User-Agent").map(str::to_string),
+            peer_ultrapeer: headers.get("X-Ultrapeer").and_then(parse_bool),
+            query_routing,
+            deflate_incoming: self.config.deflate
+                && headers.has_token("Content-Encoding", "deflate"),
+            deflate_outgoing: self.config.deflate
+                && headers.has_token("Accept-Encoding", "deflate"),
+            peer_listen_addr: headers
+                .get("Listen-IP")
+                .and_then(|addr| addr.trim().parse().ok()),
+            our_ip: headers
+                .get("Remote-IP")
+                .and_then(|ip| ip.trim().parse().ok()),
+        }
+    }
+
+    /// Feeds bytes received from the peer.
+    ///
+    /// Bytes past the end of the handshake are kept for [Handshake::into_outcome].
+    /// The handshake can't go on after an error, so the connection should be closed.
+    pub fn receive(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.input.extend_from_slice(data);
+        self.process_input()
+    }
+
+    /// Handles the next message in the input if one is expected and complete.
+    fn process_input(&mut self) -> Result<(), Error> {
+        match self.state {
+            State::AwaitingResponse | State::AwaitingConnect | State::AwaitingFinal(_) => {}
+            State::AwaitingDecision(_) | State::Done(_) => return Ok(()),
+        }
+
+        let (message, bytes_parsed) = match HandshakeMessage::parse(&self.input)? {
+            Some(parsed) => parsed,
+            None => return Ok(()),
+        };
+
+        self.input.drain(..bytes_parsed);
+
+        if !is_supported_version(message.start_line.version()) {
+            return Err(Error::unexpected(format!(
+                "unsupported protocol version {:?}",
+                message.start_line.version()
+            )));
+        }
+
+        match (
+            mem::replace(&mut self.state, State::AwaitingConnect),
+            message,
+        ) {
+            (
+                State::AwaitingConnect,
+                message @ HandshakeMessage {
+                    start_line: StartLine::Connect { .. },
+                    ..
+                },
+            ) => {
+                self.state = State::AwaitingDecision(message);
+            }
+            (State::AwaitingResponse, message) => {
+                let status = match message.status() {
+                    Some(status) => status.clone(),
+                    None => return Err(Error::unexpected("expected a response, found a CONNECT")),
+                };
+
+                if !status.is_ok() {
+                    self.state = State::Done(Outcome::Rejected {
+                        status,
+                        x_try: parse_x_try(&message.headers),
+                    });
+                    return Ok(());
+                }
+
+                let negotiated = self.negotiate(&message.headers);
+                let mut headers = Headers::new();
+
+                if negotiated.deflate_outgoing {
+                    headers.insert("Content-Encoding", "deflate");
+                }
+
+                self.output
+                    .extend_from_slice(&response(Status::ok(), headers).to_bytes());
+                self.state = State::Done(Outcome::Connected(negotiated));
+            }
+            (State::AwaitingFinal(mut negotiated), message) => {
+                let status = match message.status() {
+                    Some(status) => status.clone(),
+                    None => return Err(Error::unexpected("expected a response, found a CONNECT")),
+                };
+
+                if !status.is_ok() {
+                    self.state = State::Done(Outcome::Rejected {
+                        status,
+                        x_try: parse_x_try(&message.headers),
+                    });
+                    return Ok(());
+                }
+
+                for (name, value) in message.headers.iter() {
+                    negotiated.headers.insert(name, value);
+                }
+
+                negotiated.deflate_incoming =
+                    self.config.deflate && message.headers.has_token("Content-Encoding", "deflate");
+                self.state = State::Done(Outcome::Connected(negotiated));
+            }
+            (_, _) => return Err(Error::unexpected("expected a CONNECT, found a response")),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `GNUTELLA CONNECT` received while waiting
+    /// for [Handshake::accept] or [Handshake::reject].
+    pub fn request(&self) -> Option<&HandshakeMessage> {
+        match self.state {
+            State::AwaitingDecision(ref message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Accepts the connection requested by the peer, see [Handshake::request].
+    pub fn accept(&mut self) -> Result<(), Error> {
+        let connect = match mem::replace(&mut self.state, State::AwaitingConnect) {
+            State::AwaitingDecision(connect) => connect,
+            state => {
+                self.state = state;
+                return Err(Error::unexpected("no connection request to accept"));
+            }
+        };
+
+        let negotiated = self.negotiate(&connect.headers);
+        let mut headers = Handshake::our_headers(&self.config);
+
+        if negotiated.deflate_outgoing {
+            headers.insert("Content-Encoding", "deflate");
+        }
+
+        self.output
+            .extend_from_slice(&response(Status::ok(), headers).to_bytes());
+        self.state = State::AwaitingFinal(negotiated);
+
+        // The final response may have arrived along with the request.
+        self.process_input()
+    }
+
+    /// Rejects the connection requested by the peer with `status`,
+    /// suggesting the servents of [HandshakeConfig::x_try].
+    pub fn reject(&mut self, status: Status) -> Result<(), Error> {
+        if self.request().is_none() {
+            return Err(Error::unexpected("no connection request to reject"));
+        }
+
+        let mut headers = Headers::new();
+        headers.insert("User-Agent", self.config.user_agent.as_str());
+
+        if !self.config.x_try.is_empty() {
+            let x_try: Vec<_> = self.config.x_try.iter().map(ToString::to_string).collect();
+            headers.insert("X-Try", x_try.join(","));
+        }
+
+        self.output
+            .extend_from_slice(&response(status.clone(), headers).to_bytes());
+        self.state = State::Done(Outcome::Rejected {
+            status,
+            x_try: self.config.x_try.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Takes the bytes to send to the peer.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        mem::take(&mut self.output)
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        match self.state {
+            State::Done(ref outcome) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    /// Returns the outcome along with the bytes received after
+    /// the handshake, or `None` if it isn't done yet.
+    pub fn into_outcome(self) -> Option<(Outcome, Vec<u8>)> {
+        match self.state {
+            State::Done(outcome) => Some((outcome, self.input)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Handshake, HandshakeConfig, Outcome};
+    use crate::handshake::{Error, Status};
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    /// Passes the output of `from` to `to`.
+    fn transfer(from: &mut Handshake, to: &mut Handshake) {
+        let output = from.take_output();
+
+        if let Err(err) = to.receive(&output) {
+            panic!("{}", err);
+        }
+    }
+
+    #[test]
+    fn test_handshake_connected() {
+        let mut initiator = Handshake::initiate(HandshakeConfig {
+            listen_addr: Some(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                6346,
+            )),
+            deflate: true,
+            ..HandshakeConfig::default()
+        });
+        let mut acceptor = Handshake::respond(HandshakeConfig {
+            user_agent: "Acceptor/1.0".to_string(),
+            ultrapeer: true,
+            deflate: true,
+            remote_ip: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+            ..HandshakeConfig::default()
+        });
+
+        transfer(&mut initiator, &mut acceptor);
+
+        match acceptor.request() {
+            Some(request) => assert!(request.headers.contains("listen-ip")),
+            None => panic!("no request"),
+        }
+
+        if let Err(err) = acceptor.accept() {
+            panic!("{}", err);
+        }
+
+        transfer(&mut acceptor, &mut initiator);
+
+        // The first descriptor arrives right after the final response.
+        let mut output = initiator.take_output();
+        output.extend_from_slice(&[0xab; 4]);
+
+        if let Err(err) = acceptor.receive(&output) {
+            panic!("{}", err);
+        }
+
+        match initiator.into_outcome() {
+            Some((Outcome::Connected(negotiated), rest)) => {
+                assert!(rest.is_empty());
+                assert_eq!(negotiated.user_agent.as_deref(), Some("Acceptor/1.0"));
+                assert_eq!(negotiated.peer_ultrapeer, Some(true));
+                assert_eq!(negotiated.query_routing.as_deref(), Some("0.1"));
+                assert!(negotiated.deflate_incoming && negotiated.deflate_outgoing);
+                assert_eq!(
+                    negotiated.our_ip,
+                    Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+                );
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match acceptor.into_outcome() {
+            Some((Outcome::Connected(negotiated), rest)) => {
+                assert_eq!(rest, [0xab; 4]);
+                assert_eq!(negotiated.peer_ultrapeer, Some(false));
+                assert!(negotiated.deflate_incoming && negotiated.deflate_outgoing);
+                assert_eq!(
+                    negotiated.peer_listen_addr,
+                    Some(SocketAddr::new(
+                        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                        6346
+                    ))
+                );
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_handshake_rejected() {
+        let x_try: SocketAddr = match "1.2.3.4:6346".parse() {
+            Ok(addr) => addr,
+            Err(err) => panic!("{}", err),
+        };
+
+        let mut initiator = Handshake::initiate(HandshakeConfig {
+            deflate: false,
+            ..HandshakeConfig::default()
+        });
+        let mut acceptor = Handshake::respond(HandshakeConfig {
+            x_try: vec![x_try],
+            ..HandshakeConfig::default()
+        });
+
+        transfer(&mut initiator, &mut acceptor);
+
+        if let Err(err) = acceptor.reject(Status::busy()) {
+            panic!("{}", err);
+        }
+
+        transfer(&mut acceptor, &mut initiator);
+
+        assert!(initiator.take_output().is_empty());
+        assert_eq!(
+            initiator.outcome(),
+            Some(&Outcome::Rejected {
+                status: Status::busy(),
+                x_try: vec![x_try],
+            })
+        );
+    }
+
+    #[test]
+    fn test_handshake_unexpected() {
+        let mut acceptor = Handshake::respond(HandshakeConfig::default());
+
+        match acceptor.receive(b"GNUTELLA/0.6 200 OK\r\n\r\n") {
+            Err(Error::Unexpected { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let mut acceptor = Handshake::respond(HandshakeConfig::default());
+
+        match acceptor.receive(b"GNUTELLA CONNECT/0.4\n\n") {
+            Err(Error::Unexpected { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert!(acceptor.accept().is_err());
+    }
+}
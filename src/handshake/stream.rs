@@ -0,0 +1,116 @@
+use super::{Error, Handshake, HandshakeMessage, Outcome, Status};
+use std::io::{Read, Write};
+
+/// Runs `handshake` over a blocking `stream`, e.g., a `TcpStream`.
+///
+/// `decide` is called with the `GNUTELLA CONNECT` on the accepting side
+/// and returns `Err` with the status to reject it with.
+///
+/// Returns the outcome along with the bytes read past the handshake,
+/// which are the start of the descriptors sent by the peer and should
+/// be fed to a [MessageDecoder](crate::codec::MessageDecoder).
+pub fn run<S, F>(
+    mut handshake: Handshake,
+    stream: &mut S,
+    mut decide: F,
+) -> Result<(Outcome, Vec<u8>), Error>
+where
+    S: Read + Write + ?Sized,
+    F: FnMut(&HandshakeMessage) -> Result<(), Status>,
+{
+    let mut buf = [0; 1024];
+
+    loop {
+        let output = handshake.take_output();
+
+        if !output.is_empty() {
+            stream.write_all(&output)?;
+            stream.flush()?;
+        }
+
+        if handshake.outcome().is_some() {
+            return handshake
+                .into_outcome()
+                .ok_or_else(|| Error::unexpected("handshake done without an outcome"));
+        }
+
+        if let Some(request) = handshake.request() {
+            match decide(request) {
+                Ok(()) => handshake.accept()?,
+                Err(status) => handshake.reject(status)?,
+            }
+
+            continue;
+        }
+
+        let bytes_read = stream.read(&mut buf)?;
+
+        if bytes_read == 0 {
+            return Err(Error::Closed);
+        }
+
+        handshake.receive(&buf[..bytes_read])?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::handshake::{Handshake, HandshakeConfig, Outcome, Status};
+    use std::{
+        io::Write,
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    #[test]
+    fn test_handshake_over_tcp() {
+        let listener = match TcpListener::bind("127.0.0.1:0") {
+            Ok(listener) => listener,
+            Err(err) => panic!("{}", err),
+        };
+
+        let addr = match listener.local_addr() {
+            Ok(addr) => addr,
+            Err(err) => panic!("{}", err),
+        };
+
+        let acceptor = thread::spawn(move || {
+            let (mut stream, _) = listener.accept()?;
+            let handshake = Handshake::respond(HandshakeConfig::default());
+            let res = run(handshake, &mut stream, |request| {
+                match request.headers.get("User-Agent") {
+                    Some(_) => Ok(()),
+                    None => Err(Status::busy()),
+                }
+            });
+
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(res?)
+        });
+
+        let mut stream = match TcpStream::connect(addr) {
+            Ok(stream) => stream,
+            Err(err) => panic!("{}", err),
+        };
+
+        match run(
+            Handshake::initiate(HandshakeConfig::default()),
+            &mut stream,
+            |_| Ok(()),
+        ) {
+            Ok((Outcome::Connected(_), rest)) => assert!(rest.is_empty()),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        if let Err(err) = stream.write_all(b"descriptors") {
+            panic!("{}", err);
+        }
+
+        match acceptor.join() {
+            Ok(Ok((Outcome::Connected(negotiated), _))) => {
+                assert!(negotiated.user_agent.is_some())
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! RFC 4648 base32 without padding, as used by `urn:sha1:` and `urn:bitprint:`.
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = buffer << 8 | u16::from(byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(char::from(ALPHABET[usize::from(buffer >> bits & 0x1f)]));
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(char::from(
+            ALPHABET[usize::from(buffer << (5 - bits) & 0x1f)],
+        ));
+    }
+
+    encoded
+}
+
+/// Decodes `encoded`, ignoring case. Returns `None` if it contains
+/// characters outside the alphabet or has a dangling partial byte.
+pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+
+    for c in encoded.bytes() {
+        let value = match c.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => c - b'A',
+            c @ b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+
+        buffer = buffer << 5 | u16::from(value);
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buffer >> bits) as u8);
+        }
+    }
+
+    // Leftover bits only pad the last character and have to be zero.
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn test_base32_round_trip() {
+        let cases: [(&[u8], &str); 4] = [
+            (b"", ""),
+            (b"f", "MY"),
+            (b"foob", "MZXW6YQ"),
+            (b"foobar", "MZXW6YTBOI"),
+        ];
+
+        for &(data, encoded) in &cases {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded).as_deref(), Some(data));
+            assert_eq!(decode(&encoded.to_lowercase()).as_deref(), Some(data));
+        }
+
+        assert_eq!(decode("MZ"), None);
+        assert_eq!(decode("M1"), None);
+    }
+}
@@ -0,0 +1,306 @@
+use super::{Sha1, Urn};
+use crate::ggep::{GgepBlock, GGEP_MAGIC};
+use crate::transmittable::{Deserializable, Error, Serializable, Transmittable};
+use std::{io::Write, str};
+
+/// Byte separating the extensions of a HUGE extension block.
+pub const EXTENSION_SEPARATOR: u8 = 0x1c;
+
+/// An extension found in a HUGE extension block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Extension {
+    /// A `urn:` string, like `urn:sha1:...`.
+    Urn(Urn),
+    Ggep(GgepBlock),
+    /// Any other UTF-8 string, like XML metadata.
+    Text(String),
+    /// Bytes that aren't valid UTF-8, kept as they are.
+    Unknown(Vec<u8>),
+}
+
+/// The extensions a Query or a QueryHit result carries after its NUL-terminated
+/// string, as described by HUGE (Hash/URN Gnutella Extensions).
+///
+/// ```text
+/// +-----------------+------+------------+------+-----+
+/// | urn:sha1:BASE32 | 0x1C | GGEP block | 0x1C | XML |
+/// +-----------------+------+------------+------+-----+
+/// ```
+///
+/// A GGEP block knows its own length, so the separator following
+/// it is optional. Empty extensions are skipped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtensionBlock {
+    pub extensions: Vec<Extension>,
+}
+
+impl ExtensionBlock {
+    pub fn new() -> ExtensionBlock {
+        ExtensionBlock::default()
+    }
+
+    pub fn push(&mut self, extension: Extension) {
+        self.extensions.push(extension);
+    }
+
+    /// Returns the URNs in the block, not counting the ones inside GGEP blocks.
+    pub fn urns(&self) -> impl Iterator<Item = &Urn> {
+        self.extensions
+            .iter()
+            .filter_map(|extension| match extension {
+                Extension::Urn(urn) => Some(urn),
+                _ => None,
+            })
+    }
+
+    /// Returns the first GGEP block, if any.
+    pub fn ggep(&self) -> Option<&GgepBlock> {
+        self.extensions
+            .iter()
+            .find_map(|extension| match extension {
+                Extension::Ggep(block) => Some(block),
+                _ => None,
+            })
+    }
+
+    /// Returns the SHA-1 of the file, looking at the URNs first
+    /// and then at the `H` extension of the GGEP blocks.
+    ///
+    /// A malformed `H` extension is treated as absent.
+    pub fn sha1(&self) -> Option<Sha1> {
+        self.urns().find_map(Urn::sha1).or_else(|| {
+            self.extensions
+                .iter()
+                .find_map(|extension| match extension {
+                    Extension::Ggep(block) => block
+                        .hash()
+                        .ok()
+                        .flatten()
+                        .and_then(|hash| Urn::from(hash).sha1()),
+                    _ => None,
+                })
+        })
+    }
+}
+
+impl From<Vec<Extension>> for ExtensionBlock {
+    fn from(extensions: Vec<Extension>) -> ExtensionBlock {
+        ExtensionBlock { extensions }
+    }
+}
+
+/// Checks that `bytes` can be written as a string extension
+/// and read back as the same extension.
+fn check_string_extension(bytes: &[u8]) -> Result<(), Error> {
+    if bytes.is_empty() {
+        return Err(Error::serialization_failed("extension is empty"));
+    }
+
+    if bytes[0] == GGEP_MAGIC {
+        return Err(Error::serialization_failed(
+            "extension starts with the GGEP magic",
+        ));
+    }
+
+    if bytes.contains(&EXTENSION_SEPARATOR) || bytes.contains(&0) {
+        return Err(Error::serialization_failed(
+            "extension contains a separator or a NUL",
+        ));
+    }
+
+    Ok(())
+}
+
+impl Serializable for Extension {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        let bytes = match self {
+            Extension::Urn(urn) => urn.to_string().into_bytes(),
+            Extension::Ggep(block) => return block.serialize_into(w),
+            Extension::Text(text) => text.as_bytes().to_vec(),
+            Extension::Unknown(bytes) => bytes.clone(),
+        };
+
+        check_string_extension(&bytes)?;
+        w.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+/// Reads a single extension, stopping at the next separator,
+/// which is left in the input.
+impl<'de> Deserializable<'de> for Extension {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        if data.first() == Some(&GGEP_MAGIC) {
+            let (block, bytes_parsed) = GgepBlock::deserialize(data)?;
+            return Ok((Extension::Ggep(block), bytes_parsed));
+        }
+
+        let len = data
+            .iter()
+            .position(|&byte| byte == EXTENSION_SEPARATOR)
+            .unwrap_or(data.len());
+        let bytes = &data[..len];
+
+        let extension = match str::from_utf8(bytes) {
+            Ok(s) if s.len() >= 4 && s.as_bytes()[..4].eq_ignore_ascii_case(b"urn:") => {
+                match s.parse() {
+                    Ok(urn) => Extension::Urn(urn),
+                    Err(err) => match err {},
+                }
+            }
+            Ok(s) => Extension::Text(s.to_string()),
+            Err(_) => Extension::Unknown(bytes.to_vec()),
+        };
+
+        Ok((extension, len))
+    }
+}
+
+impl Transmittable for Extension {}
+
+impl Serializable for ExtensionBlock {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        for (index, extension) in self.extensions.iter().enumerate() {
+            if index > 0 {
+                w.write_all(&[EXTENSION_SEPARATOR])?;
+            }
+
+            extension
+                .serialize_into(w)
+                .map_err(|e| e.in_field(&index.to_string()).in_field("extensions"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExtensionBlock {
+    /// Parses `data` up to the first malformed extension, returning the
+    /// extensions before it along with the error, if any.
+    pub fn deserialize_partial(data: &[u8]) -> (ExtensionBlock, Option<Error>) {
+        let mut block = ExtensionBlock::new();
+        let mut start = 0;
+
+        while start < data.len() {
+            if data[start] == EXTENSION_SEPARATOR {
+                start += 1;
+                continue;
+            }
+
+            let index = block.extensions.len();
+
+            match Extension::deserialize(&data[start..]) {
+                Ok((extension, bytes_parsed)) => {
+                    block.extensions.push(extension);
+                    start += bytes_parsed;
+                }
+                Err(err) => {
+                    let err = err
+                        .within(&index.to_string(), 0)
+                        .within("extensions", start);
+                    return (block, Some(err));
+                }
+            }
+        }
+
+        (block, None)
+    }
+}
+
+/// Takes all of the input, like a `#[transmittable(rest)]` field.
+impl<'de> Deserializable<'de> for ExtensionBlock {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        match ExtensionBlock::deserialize_partial(data) {
+            (block, None) => Ok((block, data.len())),
+            (_, Some(err)) => Err(err),
+        }
+    }
+}
+
+impl Transmittable for ExtensionBlock {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Extension, ExtensionBlock};
+    use crate::ggep::{GgepBlock, GgepHash};
+    use crate::huge::{Sha1, Urn};
+    use crate::transmittable::{Deserializable, Error, Serializable};
+
+    #[test]
+    fn test_extension_block_transmittable() {
+        let mut ggep = GgepBlock::new();
+
+        if let Err(err) = ggep.set_hash(GgepHash::Sha1([0x02; 20])) {
+            panic!("{}", err);
+        }
+
+        let block = ExtensionBlock::from(vec![
+            Extension::Urn(Urn::Sha1(Sha1([0x01; 20]))),
+            Extension::Ggep(ggep),
+            Extension::Text("<?xml version=\"1.0\"?>".to_string()),
+            Extension::Unknown(vec![0xff, 0xfe]),
+        ]);
+
+        let block_serialized = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert!(block_serialized.starts_with(b"urn:sha1:AEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIB\x1c\xc3"));
+
+        match ExtensionBlock::deserialize(&block_serialized) {
+            Ok((block_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, block_serialized.len());
+                assert_eq!(block_deserialized, block);
+                assert_eq!(block_deserialized.sha1(), Some(Sha1([0x01; 20])));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_extension_block_separators() {
+        let mut ggep = GgepBlock::new();
+
+        if let Err(err) = ggep.set_hash(GgepHash::Sha1([0x02; 20])) {
+            panic!("{}", err);
+        }
+
+        let ggep_serialized = match ggep.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        // No separator after the GGEP block and an empty extension.
+        let mut data = ggep_serialized;
+        data.extend_from_slice(b"urn:\x1c\x1c");
+
+        match ExtensionBlock::deserialize(&data) {
+            Ok((block, _)) => {
+                assert_eq!(block.extensions.len(), 2);
+                assert_eq!(block.ggep(), Some(&ggep));
+                assert_eq!(block.urns().next(), Some(&Urn::Other("urn:".to_string())));
+                assert_eq!(block.sha1(), Some(Sha1([0x02; 20])));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_extension_block_errors() {
+        match ExtensionBlock::deserialize(b"urn:\x1c\xc3\x85") {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "extensions.1.extensions.0.id");
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let block = ExtensionBlock::from(vec![Extension::Text("a\x1cb".to_string())]);
+
+        match block.serialize() {
+            Err(Error::SerializationFailed { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! HUGE (Hash/URN Gnutella Extensions), which identify files by hash
+//! in Queries and QueryHits.
+
+pub mod base32;
+mod block;
+mod urn;
+
+pub use block::{Extension, ExtensionBlock, EXTENSION_SEPARATOR};
+pub use urn::{Bitprint, Sha1, Urn};
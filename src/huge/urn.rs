@@ -0,0 +1,164 @@
+use super::base32;
+use crate::ggep::GgepHash;
+use std::{convert::TryInto, fmt, str::FromStr};
+
+/// SHA-1 of a file, written as `urn:sha1:` followed by 32 base32 characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sha1(pub [u8; 20]);
+
+/// SHA-1 and Tiger tree root of a file, written as `urn:bitprint:`
+/// followed by their base32 separated by a dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bitprint {
+    pub sha1: Sha1,
+    pub tiger: [u8; 24],
+}
+
+/// A URN found in a HUGE extension block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Urn {
+    Sha1(Sha1),
+    Bitprint(Bitprint),
+    /// Any other URN, kept as is. Queries send `urn:sha1:` or `urn:`
+    /// without a hash to ask for hashes in the results.
+    Other(String),
+}
+
+impl Urn {
+    /// Returns the SHA-1 of the file named by the URN, if it has one.
+    pub fn sha1(&self) -> Option<Sha1> {
+        match self {
+            Urn::Sha1(sha1) => Some(*sha1),
+            Urn::Bitprint(bitprint) => Some(bitprint.sha1),
+            Urn::Other(_) => None,
+        }
+    }
+}
+
+/// Returns the rest of `s` if it starts with `prefix`, ignoring ASCII case.
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn decode_sha1(encoded: &str) -> Option<Sha1> {
+    let bytes = base32::decode(encoded)?;
+    bytes.as_slice().try_into().ok().map(Sha1)
+}
+
+impl FromStr for Urn {
+    type Err = std::convert::Infallible;
+
+    /// Parses a URN, falling back to [Urn::Other] for anything
+    /// that isn't a valid SHA-1 or bitprint URN.
+    fn from_str(s: &str) -> Result<Urn, Self::Err> {
+        if let Some(sha1) = strip_prefix_ignore_case(s, "urn:sha1:").and_then(decode_sha1) {
+            return Ok(Urn::Sha1(sha1));
+        }
+
+        let bitprint = strip_prefix_ignore_case(s, "urn:bitprint:").and_then(|encoded| {
+            let dot = encoded.find('.')?;
+            let sha1 = decode_sha1(&encoded[..dot])?;
+            let tiger = base32::decode(&encoded[dot + 1..])?;
+            Some(Bitprint {
+                sha1,
+                tiger: tiger.as_slice().try_into().ok()?,
+            })
+        });
+
+        match bitprint {
+            Some(bitprint) => Ok(Urn::Bitprint(bitprint)),
+            None => Ok(Urn::Other(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Sha1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "urn:sha1:{}", base32::encode(&self.0))
+    }
+}
+
+impl fmt::Display for Bitprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "urn:bitprint:{}.{}",
+            base32::encode(&self.sha1.0),
+            base32::encode(&self.tiger)
+        )
+    }
+}
+
+impl fmt::Display for Urn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Urn::Sha1(sha1) => sha1.fmt(f),
+            Urn::Bitprint(bitprint) => bitprint.fmt(f),
+            Urn::Other(urn) => f.write_str(urn),
+        }
+    }
+}
+
+/// The GGEP `H` extension carries the same hashes in binary.
+impl From<GgepHash> for Urn {
+    fn from(hash: GgepHash) -> Urn {
+        match hash {
+            GgepHash::Sha1(sha1) => Urn::Sha1(Sha1(sha1)),
+            GgepHash::Bitprint(sha1, tiger) => Urn::Bitprint(Bitprint {
+                sha1: Sha1(sha1),
+                tiger,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bitprint, Sha1, Urn};
+
+    #[test]
+    fn test_urn_round_trip() {
+        let sha1 = Sha1([0xab; 20]);
+        let bitprint = Bitprint {
+            sha1,
+            tiger: [0x01; 24],
+        };
+
+        for urn in &[Urn::Sha1(sha1), Urn::Bitprint(bitprint)] {
+            let parsed: Urn = match urn.to_string().parse() {
+                Ok(urn) => urn,
+                Err(err) => match err {},
+            };
+
+            assert_eq!(&parsed, urn);
+            assert_eq!(parsed.sha1(), Some(sha1));
+        }
+
+        assert_eq!(
+            sha1.to_string(),
+            "urn:sha1:VOV2XK5LVOV2XK5LVOV2XK5LVOV2XK5L"
+        );
+    }
+
+    #[test]
+    fn test_urn_case_and_fallback() {
+        let urn: Urn = match "URN:SHA1:vov2xk5lvov2xk5lvov2xk5lvov2xk5l".parse() {
+            Ok(urn) => urn,
+            Err(err) => match err {},
+        };
+
+        assert_eq!(urn, Urn::Sha1(Sha1([0xab; 20])));
+
+        for s in &["urn:sha1:", "urn:", "urn:sha1:TOOSHORT"] {
+            let urn: Urn = match s.parse() {
+                Ok(urn) => urn,
+                Err(err) => match err {},
+            };
+
+            assert_eq!(urn, Urn::Other(s.to_string()));
+        }
+    }
+}
@@ -1,2 +1,15 @@
+// Lets code generated by `#[derive(Transmittable)]` refer to `gnutella::...`
+// from within this crate as well.
+extern crate self as gnutella;
+
+pub mod codec;
+pub mod ggep;
+pub mod handshake;
+pub mod huge;
+pub mod message;
+pub mod pong_cache;
+pub mod qrp;
+pub mod routing;
+pub mod servent;
 pub mod transmittable;
-pub use gnutella_transmittable_derive::Transmittable;
+pub use gnutella_transmittable_derive::{Transmittable, TransmittableFlags};
@@ -1,10 +1,9 @@
-use std::thread;
-
 use gnutella::{
-    transmittable::{Deserializable, Serializable, Transmittable},
+    servent::{Servent, ServentConfig},
+    transmittable::{Deserializable, Serializable},
     Transmittable,
 };
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
 use uuid::Uuid;
 
 #[derive(Debug, Transmittable)]
@@ -15,6 +14,14 @@ struct Test<T, U> {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `gnutella <listen addr> [peer addr]...` runs a servent.
+    let mut args = std::env::args().skip(1);
+
+    if let Some(listen_addr) = args.next() {
+        let peers = args.map(|arg| arg.parse()).collect::<Result<_, _>>()?;
+        return start_server(listen_addr.parse()?, peers);
+    }
+
     let test = Test {
         a: Uuid::new_v4(),
         b: 6_u32,
@@ -35,16 +42,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-// fn start_server() -> Result<(), Box<dyn std::error::Error>> {
-//     // Start listening to other nodes for
-//     // Ping, Pong, Query, QueryHit and Push
-//     let server_thread_join_handle = thread::Builder::new()
-//         .name("Server thread".to_string())
-//         .spawn(|| {
-//            // gnutella::start_server();
-//         })?;
+fn start_server(
+    listen_addr: SocketAddr,
+    peers: Vec<SocketAddr>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Start listening to other nodes for
+    // Ping, Pong, Query, QueryHit and Push
+    let servent = Servent::start(ServentConfig {
+        listen_addr,
+        ..ServentConfig::default()
+    })?;
+
+    println!("listening on {}", servent.local_addr());
 
-//     server_thread_join_handle.join().unwrap();
+    for peer in peers {
+        match servent.connect(peer) {
+            Ok(info) => println!("connected to {}", info.addr),
+            Err(err) => println!("failed to connect to {}: {}", peer, err),
+        }
+    }
 
-//     Ok(())
-// }
+    for event in servent.events() {
+        println!("event = {:?}", event);
+    }
+
+    Ok(())
+}
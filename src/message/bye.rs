@@ -0,0 +1,11 @@
+use crate::Transmittable;
+
+/// Bye, sent before closing a connection to tell the other
+/// servent why.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct Bye {
+    /// Status code, in the same ranges as HTTP status codes.
+    pub code: u16,
+    #[transmittable(encoding = "nul")]
+    pub message: String,
+}
@@ -0,0 +1,156 @@
+use crate::Transmittable;
+use uuid::Uuid;
+
+/// Length of a descriptor header on the wire.
+pub const HEADER_LEN: usize = 23;
+
+/// TTL given to descriptors we originate.
+pub const DEFAULT_TTL: u8 = 7;
+
+/// Largest payload length accepted in a header. Servents drop
+/// connections sending anything bigger, as that is either garbage
+/// or an attempt to make us allocate.
+pub const MAX_PAYLOAD_LEN: u32 = 64 * 1024;
+
+/// Type of the payload following a [Header].
+///
+/// Deserializing a header with a type not listed here fails. The
+/// payload length can still be read from bytes 19..23 of the header
+/// to skip over such a descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Transmittable)]
+#[transmittable(tag = u8)]
+pub enum PayloadType {
+    Ping = 0x00,
+    Pong = 0x01,
+    Bye = 0x02,
+    RouteTableUpdate = 0x30,
+    Push = 0x40,
+    Query = 0x80,
+    QueryHit = 0x81,
+}
+
+/// The header preceding every Gnutella descriptor.
+///
+/// ```text
+/// 0               16      17    18     19               23
+/// +---------------+-------+-----+------+----------------+
+/// | Descriptor ID | Type  | TTL | Hops | Payload Length |
+/// +---------------+-------+-----+------+----------------+
+/// ```
+///
+/// The payload length is little endian and at most [MAX_PAYLOAD_LEN].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Transmittable)]
+pub struct Header {
+    pub descriptor_id: Uuid,
+    pub payload_type: PayloadType,
+    pub ttl: u8,
+    pub hops: u8,
+    #[transmittable(range = "..=MAX_PAYLOAD_LEN")]
+    pub payload_len: u32,
+}
+
+impl Header {
+    /// Returns a header for a descriptor we originate, with a fresh
+    /// descriptor ID, [DEFAULT_TTL] and no hops.
+    pub fn new(payload_type: PayloadType, payload_len: u32) -> Header {
+        Header::with_id(Uuid::new_v4(), payload_type, payload_len)
+    }
+
+    /// Same as [Header::new] but with the given descriptor ID, as needed
+    /// for replies, which carry the ID of the descriptor they answer.
+    pub fn with_id(descriptor_id: Uuid, payload_type: PayloadType, payload_len: u32) -> Header {
+        Header {
+            descriptor_id,
+            payload_type,
+            ttl: DEFAULT_TTL,
+            hops: 0,
+            payload_len,
+        }
+    }
+
+    /// Returns the header to forward the descriptor with, i.e., with one
+    /// less TTL and one more hop, or `None` if the TTL has run out and the
+    /// descriptor shouldn't be forwarded.
+    pub fn aged(&self) -> Option<Header> {
+        if self.ttl <= 1 {
+            return None;
+        }
+
+        Some(Header {
+            ttl: self.ttl - 1,
+            hops: self.hops.saturating_add(1),
+            ..*self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Header, PayloadType, DEFAULT_TTL, HEADER_LEN, MAX_PAYLOAD_LEN};
+    use crate::transmittable::{Deserializable, Error, Serializable};
+
+    #[test]
+    fn test_header_transmittable() {
+        let header = Header::new(PayloadType::Query, 42);
+
+        let header_serialized = match header.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(header_serialized.len(), HEADER_LEN);
+        assert_eq!(<Header as Serializable>::FIXED_SIZE, Some(HEADER_LEN));
+        assert_eq!(header_serialized[16..], [0x80, DEFAULT_TTL, 0, 42, 0, 0, 0]);
+
+        match Header::deserialize(&header_serialized) {
+            Ok((header_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, HEADER_LEN);
+                assert_eq!(header_deserialized, header);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_header_oversized_payload() {
+        let mut header_serialized = match Header::new(PayloadType::Ping, 0).serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        header_serialized[19..].copy_from_slice(&(MAX_PAYLOAD_LEN + 1).to_le_bytes());
+
+        match Header::deserialize(&header_serialized) {
+            Err(Error::InvalidValue { field, offset, .. }) => {
+                assert_eq!(field.as_str(), "payload_len");
+                assert_eq!(offset, 19);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_header_unknown_payload_type() {
+        let mut header_serialized = [0_u8; HEADER_LEN];
+        header_serialized[16] = 0x55;
+
+        match Header::deserialize(&header_serialized) {
+            Err(err) => assert_eq!(err.field().as_str(), "payload_type.tag"),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_header_aged() {
+        let mut header = Header::new(PayloadType::Ping, 0);
+        header.ttl = 2;
+
+        let header = match header.aged() {
+            Some(header) => header,
+            None => panic!("header with TTL 2 should be forwarded"),
+        };
+
+        assert_eq!((header.ttl, header.hops), (1, 1));
+        assert_eq!(header.aged(), None);
+    }
+}
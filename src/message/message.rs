@@ -0,0 +1,245 @@
+use super::{
+    Bye, Header, PayloadType, Ping, Pong, Push, Query, QueryHit, RouteTableUpdate, HEADER_LEN,
+};
+use crate::transmittable::{ensure_len, Deserializable, Error, Serializable, Transmittable};
+use std::{convert::TryFrom, io::Write};
+use uuid::Uuid;
+
+/// Payload of a descriptor, the type of which is given by its [Header].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Ping(Ping),
+    Pong(Pong),
+    Bye(Bye),
+    RouteTableUpdate(RouteTableUpdate),
+    Push(Push),
+    Query(Query),
+    QueryHit(QueryHit),
+}
+
+impl Payload {
+    pub fn payload_type(&self) -> PayloadType {
+        match self {
+            Payload::Ping(_) => PayloadType::Ping,
+            Payload::Pong(_) => PayloadType::Pong,
+            Payload::Bye(_) => PayloadType::Bye,
+            Payload::RouteTableUpdate(_) => PayloadType::RouteTableUpdate,
+            Payload::Push(_) => PayloadType::Push,
+            Payload::Query(_) => PayloadType::Query,
+            Payload::QueryHit(_) => PayloadType::QueryHit,
+        }
+    }
+
+    /// Deserializes a payload of type `payload_type`, which has to take up all of `data`.
+    pub fn deserialize_as_type(payload_type: PayloadType, data: &[u8]) -> Result<Payload, Error> {
+        let (payload, bytes_parsed) = match payload_type {
+            PayloadType::Ping => Ping::deserialize(data).map(|(p, n)| (Payload::Ping(p), n)),
+            PayloadType::Pong => Pong::deserialize(data).map(|(p, n)| (Payload::Pong(p), n)),
+            PayloadType::Bye => Bye::deserialize(data).map(|(p, n)| (Payload::Bye(p), n)),
+            PayloadType::RouteTableUpdate => {
+                RouteTableUpdate::deserialize(data).map(|(p, n)| (Payload::RouteTableUpdate(p), n))
+            }
+            PayloadType::Push => Push::deserialize(data).map(|(p, n)| (Payload::Push(p), n)),
+            PayloadType::Query => Query::deserialize(data).map(|(p, n)| (Payload::Query(p), n)),
+            PayloadType::QueryHit => {
+                QueryHit::deserialize(data).map(|(p, n)| (Payload::QueryHit(p), n))
+            }
+        }?;
+
+        if bytes_parsed != data.len() {
+            return Err(Error::invalid_value(format!(
+                "{} bytes left over after the payload",
+                data.len() - bytes_parsed
+            ))
+            .at_offset(bytes_parsed));
+        }
+
+        Ok(payload)
+    }
+}
+
+impl Serializable for Payload {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        match self {
+            Payload::Ping(ping) => ping.serialize_into(w),
+            Payload::Pong(pong) => pong.serialize_into(w),
+            Payload::Bye(bye) => bye.serialize_into(w),
+            Payload::RouteTableUpdate(update) => update.serialize_into(w),
+            Payload::Push(push) => push.serialize_into(w),
+            Payload::Query(query) => query.serialize_into(w),
+            Payload::QueryHit(query_hit) => query_hit.serialize_into(w),
+        }
+    }
+
+    fn serialized_len(&self) -> usize {
+        match self {
+            Payload::Ping(ping) => ping.serialized_len(),
+            Payload::Pong(pong) => pong.serialized_len(),
+            Payload::Bye(bye) => bye.serialized_len(),
+            Payload::RouteTableUpdate(update) => update.serialized_len(),
+            Payload::Push(push) => push.serialized_len(),
+            Payload::Query(query) => query.serialized_len(),
+            Payload::QueryHit(query_hit) => query_hit.serialized_len(),
+        }
+    }
+}
+
+/// A complete descriptor, i.e., a [Header] followed by its [Payload].
+///
+/// Serializing fails if the payload type or length in the header don't
+/// match the payload. [Message::new] and [Message::with_id] fill them in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub header: Header,
+    pub payload: Payload,
+}
+
+impl Message {
+    /// Returns a message we originate, with a fresh descriptor ID.
+    pub fn new(payload: Payload) -> Message {
+        Message::with_id(Uuid::new_v4(), payload)
+    }
+
+    /// Returns a message with the given descriptor ID, as needed for replies.
+    pub fn with_id(descriptor_id: Uuid, payload: Payload) -> Message {
+        let payload_len = u32::try_from(payload.serialized_len()).unwrap_or(u32::MAX);
+
+        Message {
+            header: Header::with_id(descriptor_id, payload.payload_type(), payload_len),
+            payload,
+        }
+    }
+}
+
+impl Serializable for Message {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        if self.header.payload_type != self.payload.payload_type() {
+            return Err(Error::serialization_failed(format!(
+                "header is for a {:?} but the payload is a {:?}",
+                self.header.payload_type,
+                self.payload.payload_type()
+            ))
+            .in_field("header.payload_type"));
+        }
+
+        let payload_len = self.payload.serialized_len();
+
+        if usize::try_from(self.header.payload_len) != Ok(payload_len) {
+            return Err(Error::serialization_failed(format!(
+                "header has a payload length of {} but the payload is {} bytes",
+                self.header.payload_len, payload_len
+            ))
+            .in_field("header.payload_len"));
+        }
+
+        self.header
+            .serialize_into(w)
+            .map_err(|e| e.in_field("header"))?;
+        self.payload
+            .serialize_into(w)
+            .map_err(|e| e.in_field("payload"))
+    }
+
+    fn serialized_len(&self) -> usize {
+        HEADER_LEN + self.payload.serialized_len()
+    }
+}
+
+impl<'de> Deserializable<'de> for Message {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        let (header, start) = Header::deserialize(data).map_err(|e| e.within("header", 0))?;
+
+        // `Header` limits the length to `MAX_PAYLOAD_LEN`, so this can't overflow.
+        let payload_len = header.payload_len as usize;
+        ensure_len(&data[start..], payload_len).map_err(|e| e.within("payload", start))?;
+
+        let payload =
+            Payload::deserialize_as_type(header.payload_type, &data[start..start + payload_len])
+                .map_err(|e| e.within("payload", start))?;
+
+        Ok((Message { header, payload }, start + payload_len))
+    }
+}
+
+impl Transmittable for Message {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Message, Payload};
+    use crate::message::{Bye, Ping, HEADER_LEN};
+    use crate::transmittable::{Deserializable, Error, Serializable};
+
+    #[test]
+    fn test_message_transmittable() {
+        let message = Message::new(Payload::Bye(Bye {
+            code: 200,
+            message: "Shutting down".to_string(),
+        }));
+
+        assert_eq!(message.header.payload_len, 16);
+
+        let message_serialized = match message.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(message_serialized.len(), HEADER_LEN + 16);
+        assert_eq!(message_serialized[16], 0x02);
+        assert_eq!(message_serialized[HEADER_LEN..HEADER_LEN + 2], [200, 0]);
+
+        match Message::deserialize(&message_serialized) {
+            Ok((message_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, message_serialized.len());
+                assert_eq!(message_deserialized, message);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        // The payload isn't there yet.
+        match Message::deserialize(&message_serialized[..HEADER_LEN + 4]) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "payload");
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_message_mismatched_header() {
+        let mut message = Message::new(Payload::Ping(Ping::default()));
+        message.header.payload_len = 3;
+
+        match message.serialize() {
+            Err(Error::SerializationFailed { field, .. }) => {
+                assert_eq!(field.as_str(), "header.payload_len")
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_message_leftover_payload() {
+        let message = Message::new(Payload::Bye(Bye {
+            code: 200,
+            message: String::new(),
+        }));
+
+        let mut message_serialized = match message.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        // A Bye with a byte after its NUL-terminated message.
+        message_serialized[19] += 1;
+        message_serialized.push(0);
+
+        match Message::deserialize(&message_serialized) {
+            Err(Error::InvalidValue { field, offset, .. }) => {
+                assert_eq!(field.as_str(), "payload");
+                assert_eq!(offset, HEADER_LEN + 3);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
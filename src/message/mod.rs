@@ -0,0 +1,24 @@
+//! Gnutella descriptors as they are sent over a connection.
+
+mod bye;
+mod header;
+#[allow(clippy::module_inception)]
+mod message;
+mod ping;
+mod pong;
+mod push;
+mod qhd;
+mod query;
+mod query_hit;
+mod route_table_update;
+
+pub use bye::Bye;
+pub use header::{Header, PayloadType, DEFAULT_TTL, HEADER_LEN, MAX_PAYLOAD_LEN};
+pub use message::{Message, Payload};
+pub use ping::Ping;
+pub use pong::Pong;
+pub use push::Push;
+pub use qhd::{QhdFlags, QhdTrailer, VendorCode};
+pub use query::{Query, QueryFlags};
+pub use query_hit::{QueryHit, QueryHitResult};
+pub use route_table_update::{Compressor, Patch, Reset, RouteTableUpdate};
@@ -0,0 +1,11 @@
+use crate::Transmittable;
+
+/// Ping, sent to discover hosts on the network.
+///
+/// A plain Ping has no payload, but it can carry a GGEP block.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Transmittable)]
+pub struct Ping {
+    /// Extension block following the (empty) Ping payload, if any.
+    #[transmittable(rest)]
+    pub extensions: Vec<u8>,
+}
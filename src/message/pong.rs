@@ -0,0 +1,52 @@
+use crate::Transmittable;
+use std::net::SocketAddrV4;
+
+/// Pong, the response to a [Ping](super::Ping), describing a host
+/// accepting connections.
+///
+/// The address is transmitted as the port followed by the IP.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct Pong {
+    #[transmittable(port_first)]
+    pub addr: SocketAddrV4,
+    pub shared_files: u32,
+    pub shared_kbytes: u32,
+    /// Extension block (usually GGEP) following the fixed fields, if any.
+    #[transmittable(rest)]
+    pub extensions: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pong;
+    use crate::transmittable::{Deserializable, Serializable};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    #[test]
+    fn test_pong_transmittable() {
+        let pong = Pong {
+            addr: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6346),
+            shared_files: 3,
+            shared_kbytes: 1024,
+            extensions: vec![],
+        };
+
+        let pong_serialized = match pong.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(
+            pong_serialized,
+            [0xca, 0x18, 10, 0, 0, 1, 3, 0, 0, 0, 0, 4, 0, 0]
+        );
+
+        match Pong::deserialize(&pong_serialized) {
+            Ok((pong_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 14);
+                assert_eq!(pong_deserialized, pong);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use crate::Transmittable;
+use std::net::SocketAddrV4;
+use uuid::Uuid;
+
+/// Push, asking a firewalled servent to connect to `addr` and
+/// upload the file at `file_index` to it.
+///
+/// The address is transmitted as the IP followed by the port.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct Push {
+    /// Servent ID of the servent that should push, from its QueryHit.
+    pub servent_id: Uuid,
+    pub file_index: u32,
+    pub addr: SocketAddrV4,
+    /// Extension block (usually GGEP) following the fixed fields, if any.
+    #[transmittable(rest)]
+    pub extensions: Vec<u8>,
+}
@@ -0,0 +1,387 @@
+use crate::ggep::GgepBlock;
+use crate::transmittable::{ensure_len, Deserializable, Error, Serializable, Transmittable};
+use crate::{Transmittable, TransmittableFlags};
+use std::{convert::TryFrom, fmt, io::Write};
+
+/// Four letters identifying the vendor of a servent, compared ignoring case.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Transmittable)]
+pub struct VendorCode(pub [u8; 4]);
+
+impl VendorCode {
+    pub const LIME: VendorCode = VendorCode(*b"LIME");
+    pub const BEAR: VendorCode = VendorCode(*b"BEAR");
+    pub const GTKG: VendorCode = VendorCode(*b"GTKG");
+    pub const RAZA: VendorCode = VendorCode(*b"RAZA");
+
+    /// Vendor codes with a known servent name.
+    const REGISTRY: [(VendorCode, &'static str); 4] = [
+        (VendorCode::LIME, "LimeWire"),
+        (VendorCode::BEAR, "BearShare"),
+        (VendorCode::GTKG, "gtk-gnutella"),
+        (VendorCode::RAZA, "Shareaza"),
+    ];
+
+    /// Returns true if both codes have the same letters, ignoring case.
+    pub fn matches(&self, other: VendorCode) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+
+    /// Returns the name of the servent using this code, if it is a known one.
+    pub fn servent_name(&self) -> Option<&'static str> {
+        VendorCode::REGISTRY
+            .iter()
+            .find(|(code, _)| self.matches(*code))
+            .map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Debug for VendorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VendorCode({})", self)
+    }
+}
+
+impl fmt::Display for VendorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &byte in &self.0 {
+            write!(f, "{}", std::ascii::escape_default(byte))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Bits of the two flag bytes starting the open data of a [QhdTrailer].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, TransmittableFlags)]
+#[transmittable(flags(
+    PUSH = 0x01,
+    BUSY = 0x04,
+    UPLOADED = 0x08,
+    SPEED_MEASURED = 0x10,
+    GGEP = 0x20,
+))]
+pub struct QhdFlags(u8);
+
+/// QHD (QueryHit Descriptor) trailer, found between the results
+/// and the servent ID of a [QueryHit](super::QueryHit).
+///
+/// ```text
+/// +-------------+----------------+-------+----------+-----------------+--------------+
+/// | Vendor code | Open data size | Flags | Controls | Extra open data | Private data |
+/// +-------------+----------------+-------+----------+-----------------+--------------+
+/// ```
+///
+/// Each flag is set in one of the two bytes and says whether it is known
+/// in the other one. [QhdFlags::PUSH] is set in `flags` and known if it is
+/// in `controls`, while all the others are set in `controls` and known
+/// if they are in `flags`. Use the accessors, e.g., [QhdTrailer::push],
+/// rather than reading the bytes directly.
+///
+/// An open data size of 0 leaves out both flag bytes. They are
+/// transmitted that way when they are both empty, there is no extra
+/// open data and [QhdTrailer::has_open_data] is false.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QhdTrailer {
+    pub vendor_code: VendorCode,
+    pub flags: QhdFlags,
+    pub controls: QhdFlags,
+    /// Transmit the flag bytes even if they are empty, as they were
+    /// in a trailer that was deserialized with them.
+    pub has_open_data: bool,
+    /// Open data following the flag bytes, like the size of
+    /// the XML metadata sent by LimeWire.
+    pub extra_open_data: Vec<u8>,
+    /// Vendor specific data following the open data, starting
+    /// with a GGEP block if [QhdTrailer::ggep_present] is true.
+    pub private_data: Vec<u8>,
+}
+
+impl QhdTrailer {
+    pub fn new(vendor_code: VendorCode) -> QhdTrailer {
+        QhdTrailer {
+            vendor_code,
+            flags: QhdFlags::empty(),
+            controls: QhdFlags::empty(),
+            has_open_data: false,
+            extra_open_data: Vec::new(),
+            private_data: Vec::new(),
+        }
+    }
+
+    /// Returns the value of a flag other than [QhdFlags::PUSH], if known.
+    fn get_flag(&self, flag: QhdFlags) -> Option<bool> {
+        if self.flags.contains(flag) {
+            Some(self.controls.contains(flag))
+        } else {
+            None
+        }
+    }
+
+    /// Sets the value of a flag other than [QhdFlags::PUSH], or marks it unknown.
+    fn set_flag(&mut self, flag: QhdFlags, value: Option<bool>) {
+        self.flags.set(flag, value.is_some());
+        self.controls.set(flag, value.unwrap_or(false));
+    }
+
+    /// Whether the servent is firewalled and needs a Push to upload.
+    pub fn push(&self) -> Option<bool> {
+        if self.controls.contains(QhdFlags::PUSH) {
+            Some(self.flags.contains(QhdFlags::PUSH))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_push(&mut self, value: Option<bool>) {
+        self.controls.set(QhdFlags::PUSH, value.is_some());
+        self.flags.set(QhdFlags::PUSH, value.unwrap_or(false));
+    }
+
+    /// Whether all of the upload slots of the servent are taken.
+    pub fn busy(&self) -> Option<bool> {
+        self.get_flag(QhdFlags::BUSY)
+    }
+
+    pub fn set_busy(&mut self, value: Option<bool>) {
+        self.set_flag(QhdFlags::BUSY, value);
+    }
+
+    /// Whether the servent has uploaded at least one file.
+    pub fn uploaded(&self) -> Option<bool> {
+        self.get_flag(QhdFlags::UPLOADED)
+    }
+
+    pub fn set_uploaded(&mut self, value: Option<bool>) {
+        self.set_flag(QhdFlags::UPLOADED, value);
+    }
+
+    /// Whether the speed of the QueryHit was measured rather than set by the user.
+    pub fn speed_measured(&self) -> Option<bool> {
+        self.get_flag(QhdFlags::SPEED_MEASURED)
+    }
+
+    pub fn set_speed_measured(&mut self, value: Option<bool>) {
+        self.set_flag(QhdFlags::SPEED_MEASURED, value);
+    }
+
+    /// Whether the private data starts with a GGEP block.
+    pub fn ggep_present(&self) -> Option<bool> {
+        self.get_flag(QhdFlags::GGEP)
+    }
+
+    pub fn set_ggep_present(&mut self, value: Option<bool>) {
+        self.set_flag(QhdFlags::GGEP, value);
+    }
+
+    /// Returns the GGEP block starting the private data, if
+    /// [QhdTrailer::ggep_present] is true.
+    pub fn ggep(&self) -> Result<Option<GgepBlock>, Error> {
+        if self.ggep_present() != Some(true) {
+            return Ok(None);
+        }
+
+        let (block, _) =
+            GgepBlock::deserialize(&self.private_data).map_err(|e| e.in_field("private_data"))?;
+        Ok(Some(block))
+    }
+
+    /// Size of the open data as transmitted.
+    fn open_data_len(&self) -> usize {
+        if !self.has_open_data
+            && self.flags.is_empty()
+            && self.controls.is_empty()
+            && self.extra_open_data.is_empty()
+        {
+            0
+        } else {
+            2 + self.extra_open_data.len()
+        }
+    }
+}
+
+impl Serializable for QhdTrailer {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        self.vendor_code
+            .serialize_into(w)
+            .map_err(|e| e.in_field("vendor_code"))?;
+
+        let open_data_len = u8::try_from(self.open_data_len()).map_err(|_| {
+            Error::serialization_failed(format!(
+                "{} bytes of extra open data don't fit in a QHD trailer",
+                self.extra_open_data.len()
+            ))
+            .in_field("extra_open_data")
+        })?;
+
+        open_data_len.serialize_into(w)?;
+
+        if open_data_len > 0 {
+            self.flags.serialize_into(w)?;
+            self.controls.serialize_into(w)?;
+            w.write_all(&self.extra_open_data)?;
+        }
+
+        w.write_all(&self.private_data)?;
+        Ok(())
+    }
+
+    fn serialized_len(&self) -> usize {
+        4 + 1 + self.open_data_len() + self.private_data.len()
+    }
+}
+
+/// Takes all of the input, which has to end where the servent ID starts.
+impl<'de> Deserializable<'de> for QhdTrailer {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        let (vendor_code, mut start) =
+            VendorCode::deserialize(data).map_err(|e| e.within("vendor_code", 0))?;
+
+        let (open_data_len, bytes_parsed) =
+            u8::deserialize(&data[start..]).map_err(|e| e.within("open_data_len", start))?;
+        start += bytes_parsed;
+
+        let open_data_len = usize::from(open_data_len);
+        let mut trailer = QhdTrailer::new(vendor_code);
+
+        if open_data_len == 1 {
+            return Err(
+                Error::invalid_value("open data of 1 byte has no room for the flags")
+                    .within("open_data_len", start - bytes_parsed),
+            );
+        }
+
+        if open_data_len > 0 {
+            ensure_len(&data[start..], open_data_len).map_err(|e| e.within("flags", start))?;
+            trailer.has_open_data = true;
+            trailer.flags = QhdFlags::from_bits_retain(data[start]);
+            trailer.controls = QhdFlags::from_bits_retain(data[start + 1]);
+            trailer.extra_open_data = data[start + 2..start + open_data_len].to_vec();
+            start += open_data_len;
+        }
+
+        trailer.private_data = data[start..].to_vec();
+        Ok((trailer, data.len()))
+    }
+}
+
+impl Transmittable for QhdTrailer {}
+
+#[cfg(test)]
+mod tests {
+    use super::{QhdFlags, QhdTrailer, VendorCode};
+    use crate::ggep::{GgepBlock, GgepExtension};
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_qhd_trailer_transmittable() {
+        let mut trailer = QhdTrailer::new(VendorCode::LIME);
+        trailer.set_push(Some(true));
+        trailer.set_busy(Some(false));
+        trailer.set_speed_measured(Some(true));
+        trailer.extra_open_data = vec![0x00, 0x00];
+
+        assert_eq!(
+            trailer.flags,
+            QhdFlags::PUSH | QhdFlags::BUSY | QhdFlags::SPEED_MEASURED
+        );
+        assert_eq!(trailer.controls, QhdFlags::PUSH | QhdFlags::SPEED_MEASURED);
+
+        let trailer_serialized = match trailer.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(trailer_serialized, b"LIME\x04\x15\x11\x00\x00");
+        assert_eq!(trailer.serialized_len(), trailer_serialized.len());
+
+        match QhdTrailer::deserialize(&trailer_serialized) {
+            Ok((trailer_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, trailer_serialized.len());
+                assert_eq!(
+                    trailer_deserialized,
+                    QhdTrailer {
+                        has_open_data: true,
+                        ..trailer.clone()
+                    }
+                );
+                assert_eq!(trailer_deserialized.push(), Some(true));
+                assert_eq!(trailer_deserialized.busy(), Some(false));
+                assert_eq!(trailer_deserialized.uploaded(), None);
+                assert_eq!(trailer_deserialized.speed_measured(), Some(true));
+                assert_eq!(trailer_deserialized.ggep_present(), None);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_qhd_trailer_empty_open_data() {
+        let data = [b'L', b'I', b'M', b'E', 2, 0, 0];
+
+        let trailer = match QhdTrailer::deserialize(&data) {
+            Ok((trailer, _)) => trailer,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert!(trailer.has_open_data);
+        assert_eq!(trailer.serialized_len(), data.len());
+
+        match trailer.serialize() {
+            Ok(bytes) => assert_eq!(bytes, data),
+            Err(err) => panic!("{}", err),
+        }
+
+        match QhdTrailer::new(VendorCode::LIME).serialize() {
+            Ok(bytes) => assert_eq!(bytes, b"LIME\x00"),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_qhd_trailer_flag_pairing() {
+        // Push is set in the first byte and known from the second, the
+        // others the other way around, so 0x01 0x04 means unknown push
+        // and unknown busy.
+        match QhdTrailer::deserialize(b"BEAR\x02\x01\x04") {
+            Ok((trailer, _)) => {
+                assert_eq!(trailer.push(), None);
+                assert_eq!(trailer.busy(), None);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        match QhdTrailer::deserialize(b"BEAR\x00\xab") {
+            Ok((trailer, _)) => {
+                assert_eq!(trailer.push(), None);
+                assert_eq!(trailer.private_data, [0xab]);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        match QhdTrailer::deserialize(b"BEAR\x01\x00") {
+            Err(err) => assert_eq!(err.field().as_str(), "open_data_len"),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_qhd_trailer_ggep() {
+        let mut block = GgepBlock::new();
+        block.insert(GgepExtension::new("BH", vec![]));
+
+        let mut trailer = QhdTrailer::new(VendorCode(*b"gtkg"));
+        trailer.set_ggep_present(Some(true));
+        trailer.private_data = match block.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        match trailer.ggep() {
+            Ok(ggep) => assert_eq!(ggep, Some(block)),
+            Err(err) => panic!("{}", err),
+        }
+
+        assert_eq!(trailer.vendor_code.servent_name(), Some("gtk-gnutella"));
+        assert_eq!(VendorCode(*b"XXXX").servent_name(), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::huge::ExtensionBlock;
+use crate::transmittable::{Deserializable, Error, Serializable};
+use crate::{Transmittable, TransmittableFlags};
+
+/// The field of a Query that used to be the minimum speed of responding
+/// servents, now a set of flags when [QueryFlags::FLAGS_PRESENT] is set.
+///
+/// The bits below the flags are the maximum number of hits wanted
+/// by some servents and are kept as they are.
+///
+/// Unlike the other fields of a descriptor it is big endian, which puts
+/// the [QueryFlags::FLAGS_PRESENT] mark in the first byte.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, TransmittableFlags)]
+#[transmittable(endian = "be")]
+#[transmittable(flags(
+    FLAGS_PRESENT = 1 << 15,
+    FIREWALLED = 1 << 14,
+    XML = 1 << 13,
+    LEAF_GUIDED = 1 << 12,
+    GGEP_H = 1 << 11,
+    OOB = 1 << 10,
+))]
+pub struct QueryFlags(u16);
+
+/// Query, a search forwarded through the network.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct Query {
+    pub flags: QueryFlags,
+    #[transmittable(encoding = "nul")]
+    pub search_criteria: String,
+    /// Extension block (HUGE, GGEP or XML) following the search criteria, if any.
+    #[transmittable(rest)]
+    pub extensions: Vec<u8>,
+}
+
+impl Query {
+    /// Parses [Query::extensions], ignoring the NUL some servents end it with.
+    pub fn extension_block(&self) -> Result<ExtensionBlock, Error> {
+        let data = match self.extensions.split_last() {
+            Some((0, data)) => data,
+            _ => &self.extensions[..],
+        };
+
+        let (block, _) = ExtensionBlock::deserialize(data).map_err(|e| e.in_field("extensions"))?;
+        Ok(block)
+    }
+
+    /// Replaces [Query::extensions] with `block`.
+    pub fn set_extension_block(&mut self, block: &ExtensionBlock) -> Result<(), Error> {
+        self.extensions = block.serialize().map_err(|e| e.in_field("extensions"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, QueryFlags};
+    use crate::huge::{Extension, ExtensionBlock, Sha1, Urn};
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_query_transmittable() {
+        let query = Query {
+            flags: QueryFlags::FLAGS_PRESENT | QueryFlags::GGEP_H,
+            search_criteria: "metallica".to_string(),
+            extensions: b"urn:sha1:".to_vec(),
+        };
+
+        let query_serialized = match query.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(query_serialized, b"\x88\x00metallica\0urn:sha1:");
+
+        match Query::deserialize(&query_serialized) {
+            Ok((query_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, query_serialized.len());
+                assert_eq!(query_deserialized, query);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_query_by_sha1() {
+        let mut query = Query {
+            flags: QueryFlags::FLAGS_PRESENT,
+            search_criteria: String::new(),
+            extensions: vec![],
+        };
+
+        let sha1 = Sha1([0x01; 20]);
+        let block = ExtensionBlock::from(vec![Extension::Urn(Urn::Sha1(sha1))]);
+
+        if let Err(err) = query.set_extension_block(&block) {
+            panic!("{}", err);
+        }
+
+        // Trailing NUL as sent by some servents.
+        query.extensions.push(0);
+
+        match query.extension_block() {
+            Ok(block) => assert_eq!(block.sha1(), Some(sha1)),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use super::QhdTrailer;
+use crate::huge::{ExtensionBlock, Sha1};
+use crate::transmittable::{
+    deserialize_counted, ensure_len, serialize_counted, serialized_len_counted, Deserializable,
+    Error, PortFirst, Serializable, SerializableAs, Transmittable,
+};
+use crate::Transmittable;
+use std::{convert::TryFrom, io::Write, net::SocketAddrV4};
+use uuid::Uuid;
+
+/// Length of the servent ID ending a QueryHit.
+const SERVENT_ID_LEN: usize = 16;
+
+/// A file matching a [Query](super::Query).
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct QueryHitResult {
+    pub file_index: u32,
+    pub file_size: u32,
+    #[transmittable(encoding = "nul")]
+    pub file_name: String,
+    /// Extension block (HUGE or GGEP) between the two NULs ending the result.
+    #[transmittable(encoding = "nul")]
+    pub extensions: Vec<u8>,
+}
+
+impl QueryHitResult {
+    /// Parses [QueryHitResult::extensions].
+    pub fn extension_block(&self) -> Result<ExtensionBlock, Error> {
+        let (block, _) =
+            ExtensionBlock::deserialize(&self.extensions).map_err(|e| e.in_field("extensions"))?;
+        Ok(block)
+    }
+
+    /// Replaces [QueryHitResult::extensions] with `block`.
+    pub fn set_extension_block(&mut self, block: &ExtensionBlock) -> Result<(), Error> {
+        self.extensions = block.serialize().map_err(|e| e.in_field("extensions"))?;
+        Ok(())
+    }
+
+    /// Returns the SHA-1 of the file, if the extensions carry it.
+    ///
+    /// Extensions after a malformed one are ignored, but the ones
+    /// before it are still looked at.
+    pub fn sha1(&self) -> Option<Sha1> {
+        let (block, _) = ExtensionBlock::deserialize_partial(&self.extensions);
+        block.sha1()
+    }
+}
+
+/// QueryHit, the response to a [Query](super::Query).
+///
+/// ```text
+/// +------+------+----+-------+---------+-------------+------------+
+/// | Hits | Port | IP | Speed | Results | QHD trailer | Servent ID |
+/// +------+------+----+-------+---------+-------------+------------+
+/// ```
+///
+/// The [QHD trailer](QhdTrailer) is only known to span from the end of
+/// the results to the servent ID, which takes the last 16 bytes, so this
+/// type has its own [Transmittable] impl instead of a derived one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryHit {
+    /// Address to download the results from, transmitted
+    /// as the port followed by the IP.
+    pub addr: SocketAddrV4,
+    /// Upload speed in kb/s.
+    pub speed: u32,
+    /// At most 255 results.
+    pub results: Vec<QueryHitResult>,
+    /// QHD trailer between the results and the servent ID, if any.
+    pub trailer: Option<QhdTrailer>,
+    /// Identifies the responding servent, to route Pushes to it.
+    pub servent_id: Uuid,
+}
+
+impl Serializable for QueryHit {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        let number_of_hits = u8::try_from(self.results.len()).map_err(|_| {
+            Error::serialization_failed(format!(
+                "{} results don't fit in a QueryHit",
+                self.results.len()
+            ))
+            .in_field("results")
+        })?;
+
+        number_of_hits.serialize_into(w)?;
+        PortFirst::serialize_as_into(&self.addr, w).map_err(|e| e.in_field("addr"))?;
+        self.speed
+            .serialize_into(w)
+            .map_err(|e| e.in_field("speed"))?;
+        serialize_counted(&self.results, Some(self.results.len()), w)
+            .map_err(|e| e.in_field("results"))?;
+        if let Some(ref trailer) = self.trailer {
+            trailer
+                .serialize_into(w)
+                .map_err(|e| e.in_field("trailer"))?;
+        }
+
+        self.servent_id.serialize_into(w)
+    }
+
+    fn serialized_len(&self) -> usize {
+        1 + PortFirst::serialized_len_as(&self.addr)
+            + self.speed.serialized_len()
+            + serialized_len_counted(&self.results)
+            + self
+                .trailer
+                .as_ref()
+                .map_or(0, Serializable::serialized_len)
+            + SERVENT_ID_LEN
+    }
+}
+
+impl<'de> Deserializable<'de> for QueryHit {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        let (number_of_hits, mut start) =
+            u8::deserialize(data).map_err(|e| e.within("number_of_hits", 0))?;
+
+        let (PortFirst(addr), bytes_parsed) =
+            PortFirst::<SocketAddrV4>::deserialize(&data[start..])
+                .map_err(|e| e.within("addr", start))?;
+        start += bytes_parsed;
+
+        let (speed, bytes_parsed) =
+            u32::deserialize(&data[start..]).map_err(|e| e.within("speed", start))?;
+        start += bytes_parsed;
+
+        let (results, bytes_parsed) =
+            deserialize_counted(&data[start..], Some(usize::from(number_of_hits)))
+                .map_err(|e| e.within("results", start))?;
+        start += bytes_parsed;
+
+        ensure_len(&data[start..], SERVENT_ID_LEN).map_err(|e| e.within("servent_id", start))?;
+
+        let servent_id_start = data.len() - SERVENT_ID_LEN;
+        let (servent_id, _) = Uuid::deserialize(&data[servent_id_start..])?;
+
+        let trailer = if start < servent_id_start {
+            let (trailer, _) = QhdTrailer::deserialize(&data[start..servent_id_start])
+                .map_err(|e| e.within("trailer", start))?;
+            Some(trailer)
+        } else {
+            None
+        };
+
+        let query_hit = QueryHit {
+            addr,
+            speed,
+            results,
+            trailer,
+            servent_id,
+        };
+
+        Ok((query_hit, data.len()))
+    }
+}
+
+impl Transmittable for QueryHit {}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryHit, QueryHitResult};
+    use crate::huge::{Bitprint, Sha1, Urn};
+    use crate::message::{QhdTrailer, VendorCode};
+    use crate::transmittable::{Deserializable, Serializable};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use uuid::Uuid;
+
+    fn query_hit() -> QueryHit {
+        QueryHit {
+            addr: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6346),
+            speed: 1000,
+            results: vec![QueryHitResult {
+                file_index: 1,
+                file_size: 4096,
+                file_name: "song.mp3".to_string(),
+                extensions: vec![],
+            }],
+            trailer: Some(QhdTrailer::new(VendorCode::LIME)),
+            servent_id: Uuid::from_bytes([0xab; 16]),
+        }
+    }
+
+    #[test]
+    fn test_query_hit_transmittable() {
+        let query_hit = query_hit();
+
+        let query_hit_serialized = match query_hit.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(query_hit_serialized[..3], [1, 0xca, 0x18]);
+        assert_eq!(query_hit.serialized_len(), query_hit_serialized.len());
+
+        match QueryHit::deserialize(&query_hit_serialized) {
+            Ok((query_hit_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, query_hit_serialized.len());
+                assert_eq!(query_hit_deserialized, query_hit);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_query_hit_missing_servent_id() {
+        let query_hit_serialized = match query_hit().serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        // Results (11 + 18 bytes) followed by only 10 bytes.
+        match QueryHit::deserialize(&query_hit_serialized[..39]) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "servent_id");
+                assert_eq!(err.offset(), Some(29));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_query_hit_result_sha1() {
+        let sha1 = Sha1([0x01; 20]);
+        let bitprint = Bitprint {
+            sha1,
+            tiger: [0x02; 24],
+        };
+
+        let mut result = query_hit().results.remove(0);
+        assert_eq!(result.sha1(), None);
+
+        result.extensions = format!("<xml/>\x1c{}", bitprint).into_bytes();
+        assert_eq!(result.sha1(), Some(sha1));
+
+        // A truncated GGEP block doesn't hide the URN before it.
+        result.extensions = format!("{}\x1c", Urn::Sha1(sha1)).into_bytes();
+        result.extensions.extend_from_slice(&[0xc3, 0x81]);
+        assert!(result.extension_block().is_err());
+        assert_eq!(result.sha1(), Some(sha1));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Transmittable;
+
+/// Route table update, by which a leaf sends its QRP table to its
+/// ultrapeers. The table itself is built and patched by
+/// [crate::qrp].
+///
+/// It is only sent to a direct neighbour, with a TTL of 1.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+#[transmittable(tag = u8)]
+pub enum RouteTableUpdate {
+    #[transmittable(tag = 0x00)]
+    Reset(Reset),
+    #[transmittable(tag = 0x01)]
+    Patch(Patch),
+}
+
+/// Clears the table of the receiver, setting all of its entries to
+/// `infinity`. Always precedes the first patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Transmittable)]
+pub struct Reset {
+    /// Number of entries in the table, a power of 2.
+    pub table_length: u32,
+    /// Entry value meaning that no keyword hashes to the slot.
+    pub infinity: u8,
+}
+
+/// How the data of a sequence of [Patch]es is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Transmittable)]
+#[transmittable(tag = u8)]
+pub enum Compressor {
+    None = 0x00,
+    /// Deflate in a zlib stream.
+    Zlib = 0x01,
+}
+
+/// One part of a patch to the table of the receiver.
+///
+/// A patch is split into `seq_size` parts numbered from 1. The data
+/// of all the parts is concatenated before being decompressed.
+#[derive(Debug, Clone, PartialEq, Eq, Transmittable)]
+pub struct Patch {
+    pub seq_no: u8,
+    pub seq_size: u8,
+    pub compressor: Compressor,
+    /// Size of each signed entry in the decompressed data, 4 or 8.
+    pub entry_bits: u8,
+    #[transmittable(rest)]
+    pub data: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compressor, Patch, Reset, RouteTableUpdate};
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_route_table_update_transmittable() {
+        let reset = RouteTableUpdate::Reset(Reset {
+            table_length: 65536,
+            infinity: 7,
+        });
+
+        match reset.serialize() {
+            Ok(bytes) => assert_eq!(bytes, [0x00, 0x00, 0x00, 0x01, 0x00, 0x07]),
+            Err(err) => panic!("{}", err),
+        }
+
+        let patch_serialized = [0x01, 0x02, 0x03, 0x01, 0x04, 0xf1, 0x0f];
+
+        match RouteTableUpdate::deserialize(&patch_serialized) {
+            Ok((patch, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, patch_serialized.len());
+                assert_eq!(
+                    patch,
+                    RouteTableUpdate::Patch(Patch {
+                        seq_no: 2,
+                        seq_size: 3,
+                        compressor: Compressor::Zlib,
+                        entry_bits: 4,
+                        data: vec![0xf1, 0x0f],
+                    })
+                );
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        match RouteTableUpdate::deserialize(&[0x02]) {
+            Err(_) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use crate::message::{Message, Payload, Ping, Pong, DEFAULT_TTL};
+use crate::servent::PeerId;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PongCacheConfig {
+    /// Pongs kept per connection, the newest ones replacing the oldest.
+    pub max_pongs_per_peer: usize,
+    /// Cached pongs sent in answer to a Ping, besides our own.
+    pub pongs_per_ping: usize,
+    /// Time after which the cache is refreshed with a new Ping broadcast.
+    /// Pongs are sent for twice as long, so that there are some left
+    /// while the replies to the new Ping come in.
+    pub refresh_interval: Duration,
+}
+
+impl Default for PongCacheConfig {
+    fn default() -> PongCacheConfig {
+        PongCacheConfig {
+            max_pongs_per_peer: 6,
+            pongs_per_ping: 10,
+            refresh_interval: Duration::from_secs(3),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedPong {
+    pong: Pong,
+    /// Hops the pong had taken when it reached us.
+    hops: u8,
+    received: Instant,
+}
+
+/// Recent pongs of each connection, used to answer Pings instead
+/// of broadcasting them, as in the Gnutella 0.6 ping scheme.
+///
+/// Pings are answered depending on their TTL and hops:
+///
+/// - TTL 1, hops 0, a keep-alive: only our own pong.
+/// - TTL 2, hops 0, a crawler ping: our own pong and the own pongs of
+///   our neighbours, i.e., the pongs they sent with no hops.
+/// - Any other: our own pong and up to [PongCacheConfig::pongs_per_ping]
+///   cached pongs from close enough for the TTL, taken in turn from
+///   each connection but the one the Ping came from. A Ping with TTL `t`
+///   would have gone `t - 1` hops past us and a pong cached with `h`
+///   hops is `h + 1` hops away, so only pongs with `h + 2 <= t` are sent.
+///
+/// Answered Pings are not forwarded. The cache is filled by broadcasting
+/// the Ping of [PongCache::refresh] whenever [PongCache::needs_refresh].
+#[derive(Debug, Default)]
+pub struct PongCache {
+    config: PongCacheConfig,
+    pongs: HashMap<PeerId, VecDeque<CachedPong>>,
+    refreshed: Option<Instant>,
+}
+
+impl PongCache {
+    pub fn new(config: PongCacheConfig) -> PongCache {
+        PongCache {
+            config,
+            pongs: HashMap::new(),
+            refreshed: None,
+        }
+    }
+
+    /// Caches `pong` received from `from` with `hops`.
+    pub fn insert(&mut self, from: PeerId, pong: Pong, hops: u8, now: Instant) {
+        if self.config.max_pongs_per_peer == 0 {
+            return;
+        }
+
+        let pongs = self.pongs.entry(from).or_default();
+
+        // The same servent reached again replaces its older pong.
+        pongs.retain(|cached| cached.pong.addr != pong.addr);
+
+        if pongs.len() >= self.config.max_pongs_per_peer {
+            pongs.pop_back();
+        }
+
+        pongs.push_front(CachedPong {
+            pong,
+            hops,
+            received: now,
+        });
+    }
+
+    /// Forgets the pongs of `peer`, to call when its connection closes.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.pongs.remove(&peer);
+    }
+
+    /// Returns true if the cache should be refreshed.
+    pub fn needs_refresh(&self, now: Instant) -> bool {
+        match self.refreshed {
+            Some(refreshed) => {
+                now.saturating_duration_since(refreshed) >= self.config.refresh_interval
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the Ping to broadcast to refresh the cache. The pongs
+    /// replying to it should be passed to [PongCache::insert].
+    pub fn refresh(&mut self, now: Instant) -> Message {
+        self.refreshed = Some(now);
+
+        let max_age = self.max_age();
+        for pongs in self.pongs.values_mut() {
+            pongs.retain(|cached| now.saturating_duration_since(cached.received) < max_age);
+        }
+        self.pongs.retain(|_, pongs| !pongs.is_empty());
+
+        let mut ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        ping.header.ttl = DEFAULT_TTL;
+        ping
+    }
+
+    /// Returns the number of pongs cached.
+    pub fn len(&self) -> usize {
+        self.pongs.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the Pongs answering `ping` received from `from`,
+    /// starting with `own`, which describes us.
+    pub fn answer(&self, from: PeerId, ping: &Message, own: &Pong, now: Instant) -> Vec<Message> {
+        let reply = |pong: &Pong, hops: u8| {
+            let mut message =
+                Message::with_id(ping.header.descriptor_id, Payload::Pong(pong.clone()));
+            // Just enough TTL to get back to the servent that pinged.
+            message.header.ttl = ping.header.hops.saturating_add(1);
+            message.header.hops = hops;
+            message
+        };
+
+        let mut replies = vec![reply(own, 0)];
+
+        match (ping.header.ttl, ping.header.hops) {
+            (1, 0) => {}
+            (2, 0) => {
+                let neighbours = self.fresh_pongs(now).filter(|(_, cached)| cached.hops == 0);
+                replies.extend(neighbours.map(|(_, cached)| reply(&cached.pong, 1)));
+            }
+            (ttl, _) => {
+                let mut queues: Vec<VecDeque<&CachedPong>> = self
+                    .pongs
+                    .iter()
+                    .filter(|(peer, _)| **peer != from)
+                    .map(|(_, pongs)| {
+                        pongs
+                            .iter()
+                            .filter(|cached| {
+                                self.is_fresh(cached, now)
+                                    && u16::from(cached.hops) + 2 <= u16::from(ttl)
+                            })
+                            .collect()
+                    })
+                    .collect();
+
+                // Take one pong of each connection in turn, for variety.
+                while replies.len() <= self.config.pongs_per_ping {
+                    let before = replies.len();
+
+                    for queue in &mut queues {
+                        if replies.len() > self.config.pongs_per_ping {
+                            break;
+                        }
+
+                        if let Some(cached) = queue.pop_front() {
+                            replies.push(reply(&cached.pong, cached.hops.saturating_add(1)));
+                        }
+                    }
+
+                    if replies.len() == before {
+                        break;
+                    }
+                }
+            }
+        }
+
+        replies
+    }
+
+    /// Age after which a cached pong is no longer sent.
+    fn max_age(&self) -> Duration {
+        self.config.refresh_interval * 2
+    }
+
+    fn is_fresh(&self, cached: &CachedPong, now: Instant) -> bool {
+        now.saturating_duration_since(cached.received) < self.max_age()
+    }
+
+    fn fresh_pongs(&self, now: Instant) -> impl Iterator<Item = (PeerId, &CachedPong)> {
+        self.pongs.iter().flat_map(move |(peer, pongs)| {
+            pongs
+                .iter()
+                .filter(move |cached| self.is_fresh(cached, now))
+                .map(move |cached| (*peer, cached))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PongCache, PongCacheConfig};
+    use crate::message::{Message, Payload, Ping, Pong};
+    use crate::servent::PeerId;
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4},
+        time::{Duration, Instant},
+    };
+
+    fn pong(last_octet: u8) -> Pong {
+        Pong {
+            addr: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, last_octet), 6346),
+            shared_files: 0,
+            shared_kbytes: 0,
+            extensions: vec![],
+        }
+    }
+
+    fn ping(ttl: u8, hops: u8) -> Message {
+        let mut ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        ping.header.ttl = ttl;
+        ping.header.hops = hops;
+        ping
+    }
+
+    fn addrs(replies: &[Message]) -> Vec<u8> {
+        replies
+            .iter()
+            .map(|reply| match reply.payload {
+                Payload::Pong(ref pong) => pong.addr.ip().octets()[3],
+                ref payload => panic!("unexpected payload {:?}", payload),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pong_cache_answer() {
+        let now = Instant::now();
+        let mut cache = PongCache::new(PongCacheConfig {
+            pongs_per_ping: 3,
+            ..PongCacheConfig::default()
+        });
+
+        cache.insert(PeerId(1), pong(1), 0, now);
+        cache.insert(PeerId(1), pong(2), 1, now);
+        cache.insert(PeerId(1), pong(3), 4, now);
+        cache.insert(PeerId(2), pong(4), 0, now);
+        cache.insert(PeerId(2), pong(4), 2, now);
+        assert_eq!(cache.len(), 4);
+
+        let own = pong(100);
+
+        // Keep-alive.
+        assert_eq!(
+            addrs(&cache.answer(PeerId(3), &ping(1, 0), &own, now)),
+            [100]
+        );
+
+        // Crawler, pong 4 was reached again with hops.
+        let mut crawled = addrs(&cache.answer(PeerId(3), &ping(2, 0), &own, now));
+        crawled.sort_unstable();
+        assert_eq!(crawled, [1, 100]);
+
+        // A forwarded Ping with TTL 1 wouldn't have gone any further.
+        assert_eq!(
+            addrs(&cache.answer(PeerId(3), &ping(1, 3), &own, now)),
+            [100]
+        );
+
+        // Not the pongs of the pinging connection, nor ones too far for the TTL.
+        let replies = cache.answer(PeerId(1), &ping(3, 2), &own, now);
+        assert_eq!(addrs(&replies), [100]);
+
+        let replies = cache.answer(PeerId(1), &ping(4, 2), &own, now);
+        assert_eq!(addrs(&replies), [100, 4]);
+        assert!(replies.iter().all(|reply| reply.header.ttl == 3));
+        assert_eq!(replies[1].header.hops, 3);
+
+        let replies = cache.answer(PeerId(3), &ping(7, 0), &own, now);
+        assert_eq!(replies.len(), 4);
+    }
+
+    #[test]
+    fn test_pong_cache_refresh() {
+        let now = Instant::now();
+        let mut cache = PongCache::default();
+
+        assert!(cache.needs_refresh(now));
+        cache.refresh(now);
+        assert!(!cache.needs_refresh(now + Duration::from_secs(1)));
+
+        cache.insert(PeerId(1), pong(1), 0, now);
+        cache.remove_peer(PeerId(1));
+        assert!(cache.is_empty());
+
+        cache.insert(PeerId(1), pong(1), 0, now);
+
+        let later = now + Duration::from_secs(6);
+        assert!(cache.needs_refresh(later));
+        assert_eq!(
+            cache
+                .answer(PeerId(2), &ping(7, 0), &pong(100), later)
+                .len(),
+            1
+        );
+
+        cache.refresh(later);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+//! Answering Pings from a cache of recent pongs rather than
+//! broadcasting each of them.
+
+mod cache;
+
+pub use cache::{PongCache, PongCacheConfig};
@@ -0,0 +1,41 @@
+use snafu::Snafu;
+use std::io;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// A reset asked for a table that is not a power of 2 or is bigger
+    /// than [MAX_TABLE_BITS](super::MAX_TABLE_BITS) allows.
+    #[snafu(display("Invalid table length {}", len))]
+    InvalidTableLength { len: u32 },
+    /// A reset gave an infinity that can't be patched with signed entries.
+    #[snafu(display("Invalid infinity {}", infinity))]
+    InvalidInfinity { infinity: u8 },
+    #[snafu(display("Patch received before any reset"))]
+    PatchBeforeReset,
+    /// A patch part was not the one following the parts received so far,
+    /// or didn't agree with them on the sequence size, compressor or
+    /// entry size. The partial patch has been dropped.
+    #[snafu(display("Expected patch part {}, found part {}", expected, found))]
+    OutOfSequence { expected: u8, found: u8 },
+    #[snafu(display("Invalid patch part {} of {}", seq_no, seq_size))]
+    InvalidSequence { seq_no: u8, seq_size: u8 },
+    #[snafu(display("Unsupported patch entry size of {} bits", bits))]
+    UnsupportedEntryBits { bits: u8 },
+    /// The data of a patch, compressed or not, is bigger than the table
+    /// could need.
+    #[snafu(display("Patch data of over {} bytes", max))]
+    PatchTooLarge { max: usize },
+    /// The decompressed patch doesn't have an entry for each slot of the table.
+    #[snafu(display("Patch of {} bytes for a table needing {}", len, expected))]
+    PatchLength { len: usize, expected: usize },
+    /// Applying the patch took an entry below 0 or above 255.
+    #[snafu(display("Patch takes entry {} out of range", index))]
+    EntryOutOfRange { index: usize },
+    /// A patch would need more parts than a sequence can number.
+    #[snafu(display("Patch would need {} parts", parts))]
+    TooManyParts { parts: usize },
+    #[snafu(display("Failed to compress patch: {}", source))]
+    Compress { source: io::Error },
+    #[snafu(display("Failed to decompress patch: {}", source))]
+    Decompress { source: io::Error },
+}
@@ -0,0 +1,105 @@
+//! The QRP hash function, mapping a keyword to a slot of a table
+//! with `2^bits` entries.
+
+/// Multiplier of the QRP hash, the 32 bit golden ratio as used by
+/// LimeWire and the servents following its reference implementation.
+const A_INT: u32 = 0x4f1b_bcdc;
+
+/// Hashes `keyword`, lowercased, to a slot of a table of `2^bits` entries.
+///
+/// Each character contributes its lowest byte, so that the result is
+/// the same as in servents hashing UTF-16 code units.
+///
+/// # Panics
+///
+/// If `bits` is not in `1..=32`.
+pub fn hash(keyword: &str, bits: u8) -> u32 {
+    let mut xor = 0_u32;
+
+    for (i, c) in keyword.chars().flat_map(char::to_lowercase).enumerate() {
+        let byte = u32::from(c) & 0xff;
+        xor ^= byte << ((i % 4) * 8);
+    }
+
+    hash_fast(xor, bits)
+}
+
+/// Reduces `x` to a slot of a table of `2^bits` entries.
+///
+/// A hash to `bits - n` bits is the hash to `bits` bits shifted right by
+/// `n`, which is what makes tables of different sizes mergeable.
+pub fn hash_fast(x: u32, bits: u8) -> u32 {
+    assert!((1..=32).contains(&bits), "QRP hash to {} bits", bits);
+
+    let product = u64::from(x.wrapping_mul(A_INT));
+    (product >> (32 - u32::from(bits))) as u32
+}
+
+/// Splits `text` into the keywords that are hashed into a table or
+/// looked up for a query: lowercase runs of letters and digits.
+pub fn keywords(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash, keywords};
+
+    #[test]
+    fn test_qrp_hash() {
+        // Vectors from the QRP specification.
+        let vectors: &[(&str, u8, u32)] = &[
+            ("", 13, 0),
+            ("eb", 13, 6791),
+            ("ebc", 13, 7082),
+            ("ebck", 13, 6698),
+            ("ebckl", 13, 3179),
+            ("ebcklm", 13, 3235),
+            ("ebcklme", 13, 6438),
+            ("ebcklmen", 13, 1062),
+            ("ebcklmenq", 13, 3527),
+            ("", 16, 0),
+            ("n", 16, 65003),
+            ("nd", 16, 54193),
+            ("ndf", 16, 4953),
+            ("ndfl", 16, 58201),
+            ("ndfla", 16, 34830),
+            ("ndflal", 16, 36910),
+            ("ndflale", 16, 34586),
+            ("ndflalem", 16, 37658),
+            ("ndflaleme", 16, 45559),
+            ("ol2j34lj", 10, 318),
+            ("asdfas23", 10, 503),
+            ("9um3o34fd", 10, 758),
+            ("a234d", 10, 281),
+            ("a3f", 10, 767),
+            ("3nja9", 10, 581),
+            ("2459345938032343", 10, 146),
+            ("7777a88a8a8a8", 10, 342),
+            ("asdfjklkj3k", 10, 861),
+            ("adfk32l", 10, 1011),
+            ("zzzzzzzzzzz", 10, 944),
+        ];
+
+        for &(keyword, bits, expected) in vectors {
+            assert_eq!(
+                hash(keyword, bits),
+                expected,
+                "hash({:?}, {})",
+                keyword,
+                bits
+            );
+        }
+
+        assert_eq!(hash("FAIL", 16), hash("fail", 16));
+        assert_eq!(hash("ebcklmenq", 13) >> 3, hash("ebcklmenq", 10));
+    }
+
+    #[test]
+    fn test_qrp_keywords() {
+        let words: Vec<_> = keywords("Metallica - Enter_Sandman (1991).mp3").collect();
+        assert_eq!(words, ["metallica", "enter", "sandman", "1991", "mp3"]);
+    }
+}
@@ -0,0 +1,21 @@
+//! QRP, the Query Routing Protocol, by which leaves tell their
+//! ultrapeers which keywords they might have hits for, so that
+//! queries are only forwarded to the leaves that could answer them.
+//!
+//! A leaf builds a [QueryRouteTable] of its keywords and sends it as the
+//! [RouteTableUpdate](crate::message::RouteTableUpdate)s returned by
+//! [QueryRouteTable::updates]. The ultrapeer rebuilds it with a
+//! [RouteTableReceiver], merges the tables of its leaves and checks
+//! queries against them with [QueryRouteTable::might_match].
+
+mod error;
+mod hash;
+mod receiver;
+mod table;
+
+pub use error::Error;
+pub use hash::{hash, hash_fast, keywords};
+pub use receiver::RouteTableReceiver;
+pub use table::{
+    QueryRouteTable, DEFAULT_INFINITY, DEFAULT_TABLE_BITS, MAX_PATCH_PART_LEN, MAX_TABLE_BITS,
+};
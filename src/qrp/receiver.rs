@@ -0,0 +1,305 @@
+use super::{table::unpack, Error, QueryRouteTable};
+use crate::message::{Compressor, Patch, RouteTableUpdate};
+use flate2::read::ZlibDecoder;
+use std::{convert::TryFrom, io::Read};
+
+/// A patch of which only the first parts have been received.
+#[derive(Debug)]
+struct PartialPatch {
+    seq_size: u8,
+    compressor: Compressor,
+    entry_bits: u8,
+    received: u8,
+    data: Vec<u8>,
+}
+
+/// The QRP table of a leaf as its ultrapeer rebuilds it from the
+/// route table updates the leaf sends.
+///
+/// Any error drops the patch being received. The table stays as it was
+/// before that patch, but the leaf should be disconnected as it can't
+/// be kept in sync anymore.
+#[derive(Debug, Default)]
+pub struct RouteTableReceiver {
+    table: Option<QueryRouteTable>,
+    partial: Option<PartialPatch>,
+}
+
+impl RouteTableReceiver {
+    pub fn new() -> RouteTableReceiver {
+        RouteTableReceiver::default()
+    }
+
+    /// Returns the table, once a reset has been received.
+    pub fn table(&self) -> Option<&QueryRouteTable> {
+        self.table.as_ref()
+    }
+
+    /// Returns true if some parts of a patch are still to come.
+    pub fn is_patching(&self) -> bool {
+        self.partial.is_some()
+    }
+
+    /// Applies `update`, returning true if the table changed, i.e., after
+    /// a reset or the last part of a patch.
+    pub fn apply(&mut self, update: &RouteTableUpdate) -> Result<bool, Error> {
+        match update {
+            RouteTableUpdate::Reset(reset) => {
+                self.partial = None;
+                self.table = Some(QueryRouteTable::from_reset(reset)?);
+                Ok(true)
+            }
+            RouteTableUpdate::Patch(patch) => {
+                let res = self.receive_part(patch);
+                if res.is_err() {
+                    self.partial = None;
+                }
+                res
+            }
+        }
+    }
+
+    fn receive_part(&mut self, patch: &Patch) -> Result<bool, Error> {
+        let table = self.table.as_ref().ok_or(Error::PatchBeforeReset)?;
+
+        if patch.seq_no == 0 || patch.seq_no > patch.seq_size {
+            return Err(Error::InvalidSequence {
+                seq_no: patch.seq_no,
+                seq_size: patch.seq_size,
+            });
+        }
+
+        if patch.entry_bits != 4 && patch.entry_bits != 8 {
+            return Err(Error::UnsupportedEntryBits {
+                bits: patch.entry_bits,
+            });
+        }
+
+        // A first part starts over, dropping whatever came before it.
+        if patch.seq_no == 1 {
+            self.partial = Some(PartialPatch {
+                seq_size: patch.seq_size,
+                compressor: patch.compressor,
+                entry_bits: patch.entry_bits,
+                received: 0,
+                data: Vec::new(),
+            });
+        }
+
+        let expected_len = table.len() * usize::from(patch.entry_bits) / 8;
+        // Deflate can expand incompressible data a little.
+        let max_len = expected_len + expected_len / 16 + 64;
+
+        // Taken out while the part is added, and put back if more are to come.
+        let mut partial = match self.partial.take() {
+            Some(partial)
+                if partial.seq_size == patch.seq_size
+                    && partial.compressor == patch.compressor
+                    && partial.entry_bits == patch.entry_bits
+                    && partial.received + 1 == patch.seq_no =>
+            {
+                partial
+            }
+            partial => {
+                return Err(Error::OutOfSequence {
+                    expected: partial.as_ref().map_or(1, |partial| partial.received + 1),
+                    found: patch.seq_no,
+                })
+            }
+        };
+
+        if partial.data.len() + patch.data.len() > max_len {
+            return Err(Error::PatchTooLarge { max: max_len });
+        }
+
+        partial.data.extend_from_slice(&patch.data);
+        partial.received = patch.seq_no;
+
+        if partial.received < partial.seq_size {
+            self.partial = Some(partial);
+            return Ok(false);
+        }
+
+        let data = match partial.compressor {
+            Compressor::None => partial.data,
+            Compressor::Zlib => {
+                let mut data = Vec::new();
+                let limit = u64::try_from(expected_len).unwrap_or(u64::MAX) + 1;
+                ZlibDecoder::new(&partial.data[..])
+                    .take(limit)
+                    .read_to_end(&mut data)
+                    .map_err(|source| Error::Decompress { source })?;
+                data
+            }
+        };
+
+        if data.len() != expected_len {
+            return Err(Error::PatchLength {
+                len: data.len(),
+                expected: expected_len,
+            });
+        }
+
+        let diffs = match unpack(&data, partial.entry_bits) {
+            Some(diffs) => diffs,
+            None => {
+                return Err(Error::UnsupportedEntryBits {
+                    bits: partial.entry_bits,
+                })
+            }
+        };
+
+        let mut patched = table.entries().to_vec();
+
+        for (index, (entry, &diff)) in patched.iter_mut().zip(&diffs).enumerate() {
+            *entry = u8::try_from(i16::from(*entry) + i16::from(diff))
+                .map_err(|_| Error::EntryOutOfRange { index })?;
+        }
+
+        if let Some(ref mut table) = self.table {
+            table.entries_mut().copy_from_slice(&patched);
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouteTableReceiver;
+    use crate::message::{Compressor, Patch, Reset, RouteTableUpdate};
+    use crate::qrp::{Error, QueryRouteTable};
+
+    /// Applies all `updates`, returning whether the last one changed the table.
+    fn apply_all(receiver: &mut RouteTableReceiver, updates: &[RouteTableUpdate]) -> bool {
+        updates
+            .iter()
+            .fold(false, |_, update| match receiver.apply(update) {
+                Ok(changed) => changed,
+                Err(err) => panic!("{}", err),
+            })
+    }
+
+    fn patch(seq_no: u8, seq_size: u8, data: Vec<u8>) -> RouteTableUpdate {
+        RouteTableUpdate::Patch(Patch {
+            seq_no,
+            seq_size,
+            compressor: Compressor::None,
+            entry_bits: 8,
+            data,
+        })
+    }
+
+    #[test]
+    fn test_route_table_receiver() {
+        let mut receiver = RouteTableReceiver::new();
+
+        let mut table = QueryRouteTable::default();
+        for i in 0..20_000 {
+            table.insert(&format!("keyword{}", i));
+        }
+
+        let updates = match table.updates(None) {
+            Ok(updates) => updates,
+            Err(err) => panic!("{}", err),
+        };
+
+        // The patch doesn't fit in a single part.
+        assert!(updates.len() > 2);
+
+        assert!(!apply_all(&mut receiver, &updates[..updates.len() - 1]));
+        assert!(receiver.is_patching());
+        assert!(apply_all(&mut receiver, &updates[updates.len() - 1..]));
+        assert!(!receiver.is_patching());
+        assert_eq!(receiver.table(), Some(&table));
+
+        // An 8 bit patch from the previous table.
+        let previous = table.clone();
+        table.entries_mut()[0] = 100;
+        table.entries_mut()[1] = 7;
+
+        let updates = match table.updates(Some(&previous)) {
+            Ok(updates) => updates,
+            Err(err) => panic!("{}", err),
+        };
+
+        match &updates[..] {
+            [RouteTableUpdate::Patch(patch)] => assert_eq!(patch.entry_bits, 8),
+            updates => panic!("unexpected updates {:?}", updates),
+        }
+
+        assert!(apply_all(&mut receiver, &updates));
+        assert_eq!(receiver.table(), Some(&table));
+    }
+
+    #[test]
+    fn test_route_table_receiver_errors() {
+        let mut receiver = RouteTableReceiver::new();
+
+        match receiver.apply(&patch(1, 1, vec![0; 4])) {
+            Err(Error::PatchBeforeReset) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let reset = RouteTableUpdate::Reset(Reset {
+            table_length: 3,
+            infinity: 7,
+        });
+
+        match receiver.apply(&reset) {
+            Err(Error::InvalidTableLength { len: 3 }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let reset = RouteTableUpdate::Reset(Reset {
+            table_length: 4,
+            infinity: 7,
+        });
+
+        assert!(apply_all(&mut receiver, &[reset]));
+
+        match receiver.apply(&patch(2, 2, vec![0; 2])) {
+            Err(Error::OutOfSequence {
+                expected: 1,
+                found: 2,
+            }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert!(!apply_all(&mut receiver, &[patch(1, 2, vec![0; 2])]));
+
+        match receiver.apply(&patch(3, 2, vec![0; 2])) {
+            Err(Error::InvalidSequence {
+                seq_no: 3,
+                seq_size: 2,
+            }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        // The partial patch was dropped.
+        assert!(!receiver.is_patching());
+
+        match receiver.apply(&patch(1, 1, vec![0; 3])) {
+            Err(Error::PatchLength {
+                len: 3,
+                expected: 4,
+            }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match receiver.apply(&patch(1, 1, vec![0xf0, 0, 0, 0])) {
+            Err(Error::EntryOutOfRange { index: 0 }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert!(apply_all(
+            &mut receiver,
+            &[patch(1, 1, vec![0xfa, 0, 0, 1])]
+        ));
+
+        match receiver.table() {
+            Some(table) => assert_eq!(table.entries(), [1, 7, 7, 8]),
+            None => panic!("no table"),
+        }
+    }
+}
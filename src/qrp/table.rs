@@ -0,0 +1,415 @@
+use super::{hash, keywords, Error};
+use crate::huge::{Sha1, Urn};
+use crate::message::{Compressor, Patch, Query, Reset, RouteTableUpdate};
+use flate2::{write::ZlibEncoder, Compression};
+use std::{convert::TryFrom, io::Write, iter};
+
+/// Size of the tables we build, in bits of the hash.
+pub const DEFAULT_TABLE_BITS: u8 = 16;
+
+/// Largest table accepted, in bits of the hash.
+pub const MAX_TABLE_BITS: u8 = 20;
+
+/// Entry value meaning that no keyword hashes to the slot.
+pub const DEFAULT_INFINITY: u8 = 7;
+
+/// Most data sent in a single [Patch], as LimeWire does.
+pub const MAX_PATCH_PART_LEN: usize = 4096;
+
+/// Entry value of a slot some keyword we share hashes to.
+const PRESENT: u8 = 1;
+
+/// A QRP table, with an entry for each of the `2^bits` values of
+/// [hash]. An entry below `infinity` means that a keyword might be
+/// found at that many hops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryRouteTable {
+    bits: u8,
+    infinity: u8,
+    entries: Vec<u8>,
+}
+
+impl QueryRouteTable {
+    /// Returns a table of `2^bits` entries with no keywords.
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is not in `1..=MAX_TABLE_BITS` or `infinity`
+    /// is not in `2..=127`.
+    pub fn new(bits: u8, infinity: u8) -> QueryRouteTable {
+        assert!(
+            (1..=MAX_TABLE_BITS).contains(&bits),
+            "QRP table of {} bits",
+            bits
+        );
+        assert!(
+            (2..=127).contains(&infinity),
+            "QRP infinity of {}",
+            infinity
+        );
+
+        QueryRouteTable {
+            bits,
+            infinity,
+            entries: vec![infinity; 1 << bits],
+        }
+    }
+
+    /// Returns the table a [Reset] asks for.
+    pub fn from_reset(reset: &Reset) -> Result<QueryRouteTable, Error> {
+        let len = reset.table_length;
+
+        if !len.is_power_of_two() || !(2..=1 << MAX_TABLE_BITS).contains(&len) {
+            return Err(Error::InvalidTableLength { len });
+        }
+
+        if !(2..=127).contains(&reset.infinity) {
+            return Err(Error::InvalidInfinity {
+                infinity: reset.infinity,
+            });
+        }
+
+        Ok(QueryRouteTable::new(
+            len.trailing_zeros() as u8,
+            reset.infinity,
+        ))
+    }
+
+    /// Returns a table of the default size holding the keywords of all `texts`,
+    /// e.g., the names of the files we share.
+    pub fn from_texts<'a>(texts: impl IntoIterator<Item = &'a str>) -> QueryRouteTable {
+        let mut table = QueryRouteTable::default();
+
+        for text in texts {
+            table.insert_text(text);
+        }
+
+        table
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Always false, a table has at least 2 entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn infinity(&self) -> u8 {
+        self.infinity
+    }
+
+    pub fn entries(&self) -> &[u8] {
+        &self.entries
+    }
+
+    pub(crate) fn entries_mut(&mut self) -> &mut [u8] {
+        &mut self.entries
+    }
+
+    /// Marks the slot of `keyword` as present.
+    pub fn insert(&mut self, keyword: &str) {
+        let slot = hash(keyword, self.bits) as usize;
+        self.entries[slot] = self.entries[slot].min(PRESENT);
+    }
+
+    /// Inserts each of the [keywords] of `text`.
+    pub fn insert_text(&mut self, text: &str) {
+        for keyword in keywords(text) {
+            self.insert(&keyword);
+        }
+    }
+
+    /// Inserts `urn:sha1:...`, which Queries by hash are routed with.
+    pub fn insert_sha1(&mut self, sha1: Sha1) {
+        self.insert(&Urn::Sha1(sha1).to_string());
+    }
+
+    /// Returns true if the slot of `keyword` is below infinity.
+    pub fn contains(&self, keyword: &str) -> bool {
+        self.entries[hash(keyword, self.bits) as usize] < self.infinity
+    }
+
+    /// Returns true if the servents behind this table might have a hit for
+    /// `query`, i.e., if the SHA-1 it asks for or all of its keywords are
+    /// in the table.
+    ///
+    /// Hash collisions make for false positives but never for false negatives.
+    pub fn might_match(&self, query: &Query) -> bool {
+        let sha1_match = query
+            .extension_block()
+            .map(|block| {
+                block
+                    .sha1()
+                    .into_iter()
+                    .any(|sha1| self.contains(&Urn::Sha1(sha1).to_string()))
+            })
+            .unwrap_or(false);
+
+        let mut keywords = keywords(&query.search_criteria).peekable();
+
+        if keywords.peek().is_none() {
+            return sha1_match;
+        }
+
+        sha1_match || keywords.all(|keyword| self.contains(&keyword))
+    }
+
+    /// Merges `other` into this table, keeping the smallest distance
+    /// of each slot, as an ultrapeer does with the tables of its leaves.
+    ///
+    /// The tables don't need to be of the same size: a slot of a table
+    /// with `n` fewer bits holds the slots of the bigger table that are
+    /// the same after a shift right by `n`.
+    ///
+    /// Distances of `other` that would be infinity or more in this table
+    /// are clamped to just below it, so that its keywords stay present.
+    pub fn merge(&mut self, other: &QueryRouteTable) {
+        let shift_other = other.bits.saturating_sub(self.bits);
+        let shift_self = self.bits.saturating_sub(other.bits);
+        let max_distance = self.infinity - 1;
+
+        if shift_other > 0 {
+            for (slot, &entry) in other.entries.iter().enumerate() {
+                if entry < other.infinity {
+                    let merged = &mut self.entries[slot >> shift_other];
+                    *merged = (*merged).min(entry.min(max_distance));
+                }
+            }
+        } else {
+            for (slot, merged) in self.entries.iter_mut().enumerate() {
+                let entry = other.entries[slot >> shift_self];
+                if entry < other.infinity {
+                    *merged = (*merged).min(entry.min(max_distance));
+                }
+            }
+        }
+    }
+
+    /// Returns the route table updates taking the receiver from `previous`,
+    /// the table it was last sent, to this one.
+    ///
+    /// Starts with a [Reset] if there is no `previous` or it has a different
+    /// size or infinity, and is empty if nothing changed. Entries are sent in
+    /// 4 bits if the changes fit and in 8 bits otherwise, compressed unless
+    /// that makes them bigger.
+    pub fn updates(
+        &self,
+        previous: Option<&QueryRouteTable>,
+    ) -> Result<Vec<RouteTableUpdate>, Error> {
+        let mut updates = Vec::new();
+
+        let reset_table;
+        let previous = match previous {
+            Some(previous) if previous.bits == self.bits && previous.infinity == self.infinity => {
+                previous
+            }
+            _ => {
+                updates.push(RouteTableUpdate::Reset(Reset {
+                    table_length: self.len() as u32,
+                    infinity: self.infinity,
+                }));
+                reset_table = QueryRouteTable::new(self.bits, self.infinity);
+                &reset_table
+            }
+        };
+
+        if self.entries == previous.entries {
+            return Ok(updates);
+        }
+
+        let diffs: Vec<i8> = self
+            .entries
+            .iter()
+            .zip(&previous.entries)
+            .map(|(&new, &old)| (i16::from(new) - i16::from(old)) as i8)
+            .collect();
+
+        let entry_bits = if diffs.iter().all(|diff| (-8..=7).contains(diff)) {
+            4
+        } else {
+            8
+        };
+
+        let packed = pack(&diffs, entry_bits);
+        let compressed = compress(&packed)?;
+
+        let (compressor, data) = if compressed.len() < packed.len() {
+            (Compressor::Zlib, compressed)
+        } else {
+            (Compressor::None, packed)
+        };
+
+        let parts = data.len().div_ceil(MAX_PATCH_PART_LEN);
+        let seq_size = u8::try_from(parts).map_err(|_| Error::TooManyParts { parts })?;
+
+        updates.extend(
+            data.chunks(MAX_PATCH_PART_LEN)
+                .zip(1..=seq_size)
+                .map(|(chunk, seq_no)| {
+                    RouteTableUpdate::Patch(Patch {
+                        seq_no,
+                        seq_size,
+                        compressor,
+                        entry_bits,
+                        data: chunk.to_vec(),
+                    })
+                }),
+        );
+
+        Ok(updates)
+    }
+}
+
+impl Default for QueryRouteTable {
+    fn default() -> QueryRouteTable {
+        QueryRouteTable::new(DEFAULT_TABLE_BITS, DEFAULT_INFINITY)
+    }
+}
+
+/// Packs the signed `diffs` in `entry_bits` each, high nibble first for 4 bits.
+fn pack(diffs: &[i8], entry_bits: u8) -> Vec<u8> {
+    match entry_bits {
+        4 => diffs
+            .chunks(2)
+            .map(|pair| {
+                let high = (pair[0] as u8) << 4;
+                let low = pair.get(1).map_or(0, |&diff| diff as u8 & 0x0f);
+                high | low
+            })
+            .collect(),
+        _ => diffs.iter().map(|&diff| diff as u8).collect(),
+    }
+}
+
+/// Reverses [pack], returning `None` for unsupported entry sizes.
+pub(crate) fn unpack(data: &[u8], entry_bits: u8) -> Option<Vec<i8>> {
+    match entry_bits {
+        4 => Some(
+            data.iter()
+                .flat_map(|&byte| {
+                    iter::once((byte as i8) >> 4).chain(iter::once(((byte << 4) as i8) >> 4))
+                })
+                .collect(),
+        ),
+        8 => Some(data.iter().map(|&byte| byte as i8).collect()),
+        _ => None,
+    }
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|source| Error::Compress { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash, pack, unpack, QueryRouteTable};
+    use crate::huge::{Extension, ExtensionBlock, Sha1, Urn};
+    use crate::message::{Compressor, Query, QueryFlags, RouteTableUpdate};
+
+    fn query(search_criteria: &str) -> Query {
+        Query {
+            flags: QueryFlags::empty(),
+            search_criteria: search_criteria.to_string(),
+            extensions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_query_route_table_might_match() {
+        let mut table = QueryRouteTable::from_texts(vec!["Metallica - Enter Sandman.mp3"]);
+        let sha1 = Sha1([0xab; 20]);
+        table.insert_sha1(sha1);
+
+        assert!(table.contains("METALLICA"));
+        assert!(table.might_match(&query("enter metallica")));
+        assert!(!table.might_match(&query("metallica unforgiven")));
+        assert!(!table.might_match(&query("")));
+
+        let mut by_hash = query("");
+        let block = ExtensionBlock::from(vec![Extension::Urn(Urn::Sha1(sha1))]);
+        if let Err(err) = by_hash.set_extension_block(&block) {
+            panic!("{}", err);
+        }
+        assert!(table.might_match(&by_hash));
+    }
+
+    #[test]
+    fn test_query_route_table_merge() {
+        let mut merged = QueryRouteTable::new(12, 7);
+        merged.insert("small");
+
+        let mut big = QueryRouteTable::new(16, 7);
+        big.insert("big");
+        let mut smaller = QueryRouteTable::new(8, 5);
+        smaller.insert("smaller");
+
+        merged.merge(&big);
+        merged.merge(&smaller);
+
+        for keyword in &["small", "big", "smaller"] {
+            assert!(merged.contains(keyword), "{}", keyword);
+        }
+
+        let present = merged.entries().iter().filter(|&&entry| entry < 7).count();
+        // "smaller" covers 16 slots of the merged table.
+        assert!(present <= 2 + 16);
+
+        // A distance that is infinity in the merged table stays present.
+        let mut far = QueryRouteTable::new(12, 127);
+        let slot = hash("far", 12) as usize;
+        far.entries_mut()[slot] = 100;
+
+        merged.merge(&far);
+        assert!(merged.contains("far"));
+        assert_eq!(merged.entries()[slot], 6);
+    }
+
+    #[test]
+    fn test_query_route_table_updates() {
+        let mut table = QueryRouteTable::new(10, 7);
+        table.insert("metallica");
+
+        let updates = match table.updates(None) {
+            Ok(updates) => updates,
+            Err(err) => panic!("{}", err),
+        };
+
+        match &updates[..] {
+            [RouteTableUpdate::Reset(reset), RouteTableUpdate::Patch(patch)] => {
+                assert_eq!(reset.table_length, 1024);
+                assert_eq!(reset.infinity, 7);
+                assert_eq!((patch.seq_no, patch.seq_size), (1, 1));
+                assert_eq!(patch.entry_bits, 4);
+                assert_eq!(patch.compressor, Compressor::Zlib);
+            }
+            updates => panic!("unexpected updates {:?}", updates),
+        }
+
+        match table.updates(Some(&table)) {
+            Ok(updates) => assert!(updates.is_empty()),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_qrp_pack() {
+        let diffs = [-8, 7, -1, 0, 3];
+        let packed = pack(&diffs, 4);
+        assert_eq!(packed, [0x87, 0xf0, 0x30]);
+        assert_eq!(unpack(&packed, 4), Some(vec![-8, 7, -1, 0, 3, 0]));
+
+        let diffs = [-128, 127];
+        assert_eq!(unpack(&pack(&diffs, 8), 8), Some(diffs.to_vec()));
+        assert_eq!(unpack(&[0], 2), None);
+    }
+}
@@ -0,0 +1,245 @@
+use super::{
+    DuplicateFilter, DuplicateFilterConfig, Route, Router, RouterConfig, TtlPolicy,
+    TtlPolicyConfig, TtlVerdict, Verdict,
+};
+use crate::message::{Message, Payload};
+use crate::servent::PeerId;
+use std::time::Instant;
+
+/// Why a descriptor was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// The same flooded descriptor already came by.
+    Duplicate,
+    /// A reply to a descriptor we don't know the route of.
+    NoRoute,
+    /// The TTL ran out before reaching where it was going.
+    Expired,
+}
+
+/// What to do with a descriptor received from a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Send it to all the peers but `except`, the one it came from.
+    /// A Ping or Query is also for us to answer.
+    Flood {
+        except: PeerId,
+    },
+    /// Send it to this peer only.
+    Forward(PeerId),
+    /// It is for us only, including a Ping or Query whose TTL ran out.
+    Deliver,
+    Drop(DropReason),
+}
+
+/// Decides where received descriptors go, keeping the state needed
+/// to route replies, drop duplicates and keep TTLs within bounds.
+#[derive(Debug, Default)]
+pub struct Dispatcher {
+    router: Router,
+    duplicates: DuplicateFilter,
+    policy: TtlPolicy,
+}
+
+impl Dispatcher {
+    pub fn new(
+        router_config: RouterConfig,
+        duplicate_config: DuplicateFilterConfig,
+        policy_config: TtlPolicyConfig,
+    ) -> Dispatcher {
+        Dispatcher {
+            router: Router::new(router_config),
+            duplicates: DuplicateFilter::new(duplicate_config),
+            policy: TtlPolicy::new(policy_config),
+        }
+    }
+
+    /// Returns what to do with `message` received from `from`.
+    ///
+    /// The header of `message` is updated to be sent on: its TTL is
+    /// clamped by the [TtlPolicy] and it is aged if it is forwarded.
+    pub fn dispatch(&mut self, from: PeerId, message: &mut Message, now: Instant) -> Action {
+        if self.policy.enforce(from, &mut message.header) == TtlVerdict::Expired {
+            return Action::Drop(DropReason::Expired);
+        }
+
+        let action = self.route(from, message, now);
+
+        match action {
+            Action::Flood { .. } | Action::Forward(_) => match message.header.aged() {
+                Some(header) => message.header = header,
+                None if matches!(action, Action::Flood { .. }) => return Action::Deliver,
+                None => return Action::Drop(DropReason::Expired),
+            },
+            Action::Deliver | Action::Drop(_) => {}
+        }
+
+        action
+    }
+
+    fn route(&mut self, from: PeerId, message: &Message, now: Instant) -> Action {
+        match message.payload {
+            Payload::Ping(_) | Payload::Query(_) => {
+                match self.duplicates.check(&message.header, now) {
+                    Verdict::New => self.router.record(message, Route::Peer(from), now),
+                    // Replies keep going over the path of the first copy.
+                    Verdict::HigherTtl => {}
+                    Verdict::Duplicate => return Action::Drop(DropReason::Duplicate),
+                }
+
+                Action::Flood { except: from }
+            }
+            Payload::Pong(_) | Payload::QueryHit(_) | Payload::Push(_) => {
+                // Pushes for the servent of this QueryHit go back to `from`.
+                self.router.record(message, Route::Peer(from), now);
+
+                match self.router.route_reply(message, now) {
+                    Some(Route::Local) => Action::Deliver,
+                    Some(Route::Peer(peer)) => Action::Forward(peer),
+                    None => Action::Drop(DropReason::NoRoute),
+                }
+            }
+            Payload::Bye(_) | Payload::RouteTableUpdate(_) => Action::Deliver,
+        }
+    }
+
+    /// Records `message`, which we are about to send, so that replies
+    /// to it are delivered to us and copies of it are dropped.
+    pub fn originate(&mut self, message: &Message, now: Instant) {
+        if let Payload::Ping(_) | Payload::Query(_) = message.payload {
+            self.duplicates.check(&message.header, now);
+        }
+
+        self.router.record(message, Route::Local, now);
+    }
+
+    /// Forgets the routes through `peer` and its violations,
+    /// to call when its connection closes.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.router.remove_peer(peer);
+        self.policy.remove_peer(peer);
+    }
+
+    /// Returns true if `peer` keeps sending descriptors with
+    /// TTLs out of bounds and should be disconnected.
+    pub fn is_abusive(&self, peer: PeerId) -> bool {
+        self.policy.is_abusive(peer)
+    }
+
+    pub fn router(&self) -> &Router {
+        &self.router
+    }
+
+    pub fn duplicates(&self) -> &DuplicateFilter {
+        &self.duplicates
+    }
+
+    pub fn policy(&self) -> &TtlPolicy {
+        &self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Dispatcher, DropReason};
+    use crate::message::{Message, Payload, Ping, Pong, Query, QueryFlags};
+    use crate::servent::PeerId;
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4},
+        time::Instant,
+    };
+
+    fn pong_for(message: &Message) -> Message {
+        Message::with_id(
+            message.header.descriptor_id,
+            Payload::Pong(Pong {
+                addr: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6346),
+                shared_files: 0,
+                shared_kbytes: 0,
+                extensions: vec![],
+            }),
+        )
+    }
+
+    #[test]
+    fn test_dispatch_flood_and_duplicates() {
+        let now = Instant::now();
+        let mut dispatcher = Dispatcher::default();
+        let query = Message::new(Payload::Query(Query {
+            flags: QueryFlags::FLAGS_PRESENT,
+            search_criteria: "test".to_string(),
+            extensions: vec![],
+        }));
+
+        assert_eq!(
+            dispatcher.dispatch(PeerId(1), &mut query.clone(), now),
+            Action::Flood { except: PeerId(1) }
+        );
+        assert_eq!(
+            dispatcher.dispatch(PeerId(2), &mut query.clone(), now),
+            Action::Drop(DropReason::Duplicate)
+        );
+        assert_eq!(dispatcher.duplicates().duplicates(), 1);
+    }
+
+    #[test]
+    fn test_dispatch_replies() {
+        let now = Instant::now();
+        let mut dispatcher = Dispatcher::default();
+
+        let ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        dispatcher.dispatch(PeerId(1), &mut ping.clone(), now);
+        assert_eq!(
+            dispatcher.dispatch(PeerId(2), &mut pong_for(&ping), now),
+            Action::Forward(PeerId(1))
+        );
+
+        let own_ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        dispatcher.originate(&own_ping, now);
+        assert_eq!(
+            dispatcher.dispatch(PeerId(2), &mut pong_for(&own_ping), now),
+            Action::Deliver
+        );
+        assert_eq!(
+            dispatcher.dispatch(PeerId(2), &mut own_ping.clone(), now),
+            Action::Drop(DropReason::Duplicate)
+        );
+
+        dispatcher.remove_peer(PeerId(1));
+        assert_eq!(
+            dispatcher.dispatch(PeerId(2), &mut pong_for(&ping), now),
+            Action::Drop(DropReason::NoRoute)
+        );
+        assert_eq!(dispatcher.router().stats().orphaned, 1);
+    }
+
+    #[test]
+    fn test_dispatch_ttl() {
+        let now = Instant::now();
+        let mut dispatcher = Dispatcher::default();
+
+        let mut ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        ping.header.ttl = 200;
+
+        assert_eq!(
+            dispatcher.dispatch(PeerId(1), &mut ping, now),
+            Action::Flood { except: PeerId(1) }
+        );
+        assert_eq!((ping.header.ttl, ping.header.hops), (6, 1));
+        assert_eq!(dispatcher.policy().violations(PeerId(1)), 1);
+
+        // Answered but not forwarded.
+        let mut ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        ping.header.ttl = 1;
+        assert_eq!(
+            dispatcher.dispatch(PeerId(1), &mut ping, now),
+            Action::Deliver
+        );
+
+        ping.header.ttl = 0;
+        assert_eq!(
+            dispatcher.dispatch(PeerId(1), &mut ping, now),
+            Action::Drop(DropReason::Expired)
+        );
+    }
+}
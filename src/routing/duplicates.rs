@@ -0,0 +1,185 @@
+use crate::message::{Header, PayloadType};
+use std::{
+    collections::HashMap,
+    mem,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateFilterConfig {
+    /// Descriptors are remembered for at least this long and at most twice as long.
+    pub window: Duration,
+    /// Descriptors remembered per window, beyond which the window
+    /// is rotated early and memory stays bounded.
+    pub max_entries: usize,
+    /// Whether a duplicate with more TTL left than any copy seen so
+    /// far is let through, as it can reach servents the others couldn't.
+    pub reforward_higher_ttl: bool,
+}
+
+impl Default for DuplicateFilterConfig {
+    fn default() -> DuplicateFilterConfig {
+        DuplicateFilterConfig {
+            window: Duration::from_secs(5 * 60),
+            max_entries: 64 * 1024,
+            reforward_higher_ttl: false,
+        }
+    }
+}
+
+/// What a [DuplicateFilter] thinks of a descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    New,
+    Duplicate,
+    /// Seen before but with less TTL left, only returned with
+    /// [DuplicateFilterConfig::reforward_higher_ttl].
+    HigherTtl,
+}
+
+/// Remembers the (GUID, payload type) of flooded descriptors for a time
+/// window, with the highest TTL each was seen with.
+///
+/// Two sets are kept and rotated every window, so that entries don't need
+/// timestamps: lookups check both and inserts go to the current one.
+#[derive(Debug)]
+pub struct DuplicateFilter {
+    config: DuplicateFilterConfig,
+    current: HashMap<(Uuid, PayloadType), u8>,
+    previous: HashMap<(Uuid, PayloadType), u8>,
+    rotated: Option<Instant>,
+    duplicates: u64,
+}
+
+impl Default for DuplicateFilter {
+    fn default() -> DuplicateFilter {
+        DuplicateFilter::new(DuplicateFilterConfig::default())
+    }
+}
+
+impl DuplicateFilter {
+    pub fn new(config: DuplicateFilterConfig) -> DuplicateFilter {
+        DuplicateFilter {
+            config,
+            current: HashMap::new(),
+            previous: HashMap::new(),
+            rotated: None,
+            duplicates: 0,
+        }
+    }
+
+    fn rotate(&mut self, now: Instant) {
+        self.previous = mem::take(&mut self.current);
+        self.rotated = Some(now);
+    }
+
+    /// Records the descriptor of `header` and tells whether it was seen before.
+    pub fn check(&mut self, header: &Header, now: Instant) -> Verdict {
+        match self.rotated {
+            Some(rotated) if now.saturating_duration_since(rotated) < self.config.window => {}
+            Some(_) => self.rotate(now),
+            None => self.rotated = Some(now),
+        }
+
+        let key = (header.descriptor_id, header.payload_type);
+        let seen_ttl = self
+            .current
+            .get(&key)
+            .or_else(|| self.previous.get(&key))
+            .copied();
+
+        let verdict = match seen_ttl {
+            None => Verdict::New,
+            Some(ttl) if self.config.reforward_higher_ttl && header.ttl > ttl => Verdict::HigherTtl,
+            Some(_) => Verdict::Duplicate,
+        };
+
+        if verdict == Verdict::Duplicate {
+            self.duplicates += 1;
+            return verdict;
+        }
+
+        if self.current.len() >= self.config.max_entries && !self.current.contains_key(&key) {
+            self.rotate(now);
+        }
+
+        let ttl = seen_ttl.map_or(header.ttl, |ttl| ttl.max(header.ttl));
+        self.current.insert(key, ttl);
+
+        verdict
+    }
+
+    /// Number of descriptors found to be duplicates.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Number of descriptors remembered.
+    pub fn len(&self) -> usize {
+        self.current.len() + self.previous.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DuplicateFilter, DuplicateFilterConfig, Verdict};
+    use crate::message::{Header, PayloadType};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_duplicate_filter_window() {
+        let start = Instant::now();
+        let mut filter = DuplicateFilter::new(DuplicateFilterConfig {
+            window: Duration::from_secs(10),
+            ..DuplicateFilterConfig::default()
+        });
+
+        let header = Header::new(PayloadType::Query, 0);
+        let pong = Header::with_id(header.descriptor_id, PayloadType::Pong, 0);
+
+        assert_eq!(filter.check(&header, start), Verdict::New);
+        assert_eq!(filter.check(&pong, start), Verdict::New);
+        assert_eq!(filter.check(&header, start), Verdict::Duplicate);
+
+        // Still remembered in the previous window, then forgotten.
+        let later = start + Duration::from_secs(15);
+        assert_eq!(
+            filter.check(&Header::new(PayloadType::Ping, 0), later),
+            Verdict::New
+        );
+        assert_eq!(filter.check(&header, later), Verdict::Duplicate);
+        assert_eq!(
+            filter.check(&header, later + Duration::from_secs(10)),
+            Verdict::New
+        );
+
+        assert_eq!(filter.duplicates(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_filter_ttl_and_bounds() {
+        let now = Instant::now();
+        let mut filter = DuplicateFilter::new(DuplicateFilterConfig {
+            max_entries: 1,
+            reforward_higher_ttl: true,
+            ..DuplicateFilterConfig::default()
+        });
+
+        let mut header = Header::new(PayloadType::Ping, 0);
+        header.ttl = 3;
+
+        assert_eq!(filter.check(&header, now), Verdict::New);
+        header.ttl = 5;
+        assert_eq!(filter.check(&header, now), Verdict::HigherTtl);
+        assert_eq!(filter.check(&header, now), Verdict::Duplicate);
+
+        filter.check(&Header::new(PayloadType::Ping, 0), now);
+        filter.check(&Header::new(PayloadType::Ping, 0), now);
+        assert_eq!(filter.len(), 2);
+    }
+}
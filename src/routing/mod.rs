@@ -0,0 +1,15 @@
+//! Routing of replies back along the path of the descriptors they answer,
+//! dropping of flooded descriptors that already came by and enforcement
+//! of TTL bounds.
+
+mod dispatch;
+mod duplicates;
+mod policy;
+mod router;
+mod table;
+
+pub use dispatch::{Action, Dispatcher, DropReason};
+pub use duplicates::{DuplicateFilter, DuplicateFilterConfig, Verdict};
+pub use policy::{TtlPolicy, TtlPolicyConfig, TtlVerdict};
+pub use router::{Router, RouterConfig, RouterStats};
+pub use table::{Route, RoutingTable};
@@ -0,0 +1,126 @@
+use crate::message::{Header, DEFAULT_TTL};
+use crate::servent::PeerId;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlPolicyConfig {
+    /// Highest TTL plus hops a descriptor may have, above which its TTL is lowered.
+    pub max_ttl: u8,
+    /// Violations after which a peer is considered abusive.
+    pub max_violations: u64,
+}
+
+impl Default for TtlPolicyConfig {
+    fn default() -> TtlPolicyConfig {
+        TtlPolicyConfig {
+            max_ttl: DEFAULT_TTL,
+            max_violations: 50,
+        }
+    }
+}
+
+/// What a [TtlPolicy] did with a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtlVerdict {
+    Accepted,
+    /// TTL plus hops was over the maximum, so the TTL was lowered from `ttl`.
+    Clamped {
+        ttl: u8,
+    },
+    /// The TTL is 0, or the hops alone reach the maximum, so the
+    /// descriptor has to be dropped.
+    Expired,
+}
+
+/// Keeps the TTL of received descriptors within bounds, counting
+/// the peers sending descriptors out of them.
+#[derive(Debug, Default)]
+pub struct TtlPolicy {
+    config: TtlPolicyConfig,
+    violations: HashMap<PeerId, u64>,
+}
+
+impl TtlPolicy {
+    pub fn new(config: TtlPolicyConfig) -> TtlPolicy {
+        TtlPolicy {
+            config,
+            violations: HashMap::new(),
+        }
+    }
+
+    /// Checks `header` of a descriptor received from `from`,
+    /// lowering its TTL if needed.
+    pub fn enforce(&mut self, from: PeerId, header: &mut Header) -> TtlVerdict {
+        let max_ttl = self.config.max_ttl.saturating_sub(header.hops);
+
+        let verdict = if header.ttl == 0 || max_ttl == 0 {
+            TtlVerdict::Expired
+        } else if header.ttl > max_ttl {
+            let ttl = header.ttl;
+            header.ttl = max_ttl;
+            TtlVerdict::Clamped { ttl }
+        } else {
+            return TtlVerdict::Accepted;
+        };
+
+        *self.violations.entry(from).or_insert(0) += 1;
+        verdict
+    }
+
+    /// Number of descriptors out of bounds received from `peer`.
+    pub fn violations(&self, peer: PeerId) -> u64 {
+        self.violations.get(&peer).copied().unwrap_or(0)
+    }
+
+    /// Returns true if `peer` sent [TtlPolicyConfig::max_violations]
+    /// descriptors out of bounds, so it should be disconnected.
+    pub fn is_abusive(&self, peer: PeerId) -> bool {
+        self.violations(peer) >= self.config.max_violations
+    }
+
+    /// Forgets the violations of `peer`, to call when its connection closes.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.violations.remove(&peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TtlPolicy, TtlPolicyConfig, TtlVerdict};
+    use crate::message::{Header, PayloadType};
+    use crate::servent::PeerId;
+
+    #[test]
+    fn test_ttl_policy_enforce() {
+        let mut policy = TtlPolicy::new(TtlPolicyConfig {
+            max_ttl: 7,
+            max_violations: 2,
+        });
+        let mut header = Header::new(PayloadType::Query, 0);
+
+        header.ttl = 5;
+        header.hops = 2;
+        assert_eq!(policy.enforce(PeerId(1), &mut header), TtlVerdict::Accepted);
+
+        header.ttl = 200;
+        assert_eq!(
+            policy.enforce(PeerId(1), &mut header),
+            TtlVerdict::Clamped { ttl: 200 }
+        );
+        assert_eq!(header.ttl, 5);
+        assert!(!policy.is_abusive(PeerId(1)));
+
+        header.ttl = 0;
+        assert_eq!(policy.enforce(PeerId(1), &mut header), TtlVerdict::Expired);
+
+        header.ttl = 1;
+        header.hops = 7;
+        assert_eq!(policy.enforce(PeerId(2), &mut header), TtlVerdict::Expired);
+
+        assert_eq!(policy.violations(PeerId(1)), 2);
+        assert!(policy.is_abusive(PeerId(1)));
+
+        policy.remove_peer(PeerId(1));
+        assert_eq!(policy.violations(PeerId(1)), 0);
+    }
+}
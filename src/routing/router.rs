@@ -0,0 +1,186 @@
+use super::{Route, RoutingTable};
+use crate::message::{Message, Payload};
+use crate::servent::PeerId;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouterConfig {
+    /// Time a route is kept after the descriptor it was learnt from.
+    pub expiry: Duration,
+    /// Routes kept per table, beyond which the oldest ones are dropped.
+    pub max_entries: usize,
+}
+
+impl Default for RouterConfig {
+    fn default() -> RouterConfig {
+        RouterConfig {
+            expiry: Duration::from_secs(10 * 60),
+            max_entries: 64 * 1024,
+        }
+    }
+}
+
+/// Counters kept by a [Router].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RouterStats {
+    /// Replies a route was found for.
+    pub routed: u64,
+    /// Replies dropped for lack of a route.
+    pub orphaned: u64,
+}
+
+/// Routes replies back along the path of the descriptors they answer.
+///
+/// Pongs follow the Ping with their descriptor ID, QueryHits follow the
+/// Query with theirs, and Pushes follow the QueryHit whose servent ID
+/// they carry.
+#[derive(Debug)]
+pub struct Router {
+    pings: RoutingTable,
+    queries: RoutingTable,
+    query_hits: RoutingTable,
+    stats: RouterStats,
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new(RouterConfig::default())
+    }
+}
+
+impl Router {
+    pub fn new(config: RouterConfig) -> Router {
+        let table = || RoutingTable::new(config.expiry, config.max_entries);
+
+        Router {
+            pings: table(),
+            queries: table(),
+            query_hits: table(),
+            stats: RouterStats::default(),
+        }
+    }
+
+    /// Remembers where `message` came from, if replies
+    /// to it have to be routed back.
+    pub fn record(&mut self, message: &Message, route: Route, now: Instant) {
+        let guid = message.header.descriptor_id;
+
+        match message.payload {
+            Payload::Ping(_) => self.pings.insert(guid, route, now),
+            Payload::Query(_) => self.queries.insert(guid, route, now),
+            Payload::QueryHit(ref query_hit) => {
+                self.query_hits.insert(query_hit.servent_id, route, now)
+            }
+            Payload::Pong(_)
+            | Payload::Bye(_)
+            | Payload::RouteTableUpdate(_)
+            | Payload::Push(_) => {}
+        }
+    }
+
+    /// Returns where to send `message` if it is a reply, counting it
+    /// as orphaned if no route is known.
+    ///
+    /// Returns `None` for descriptors that aren't routed as replies.
+    pub fn route_reply(&mut self, message: &Message, now: Instant) -> Option<Route> {
+        let guid = message.header.descriptor_id;
+
+        let route = match message.payload {
+            Payload::Pong(_) => self.pings.get(&guid, now),
+            Payload::QueryHit(_) => self.queries.get(&guid, now),
+            Payload::Push(ref push) => self.query_hits.get(&push.servent_id, now),
+            Payload::Ping(_)
+            | Payload::Query(_)
+            | Payload::Bye(_)
+            | Payload::RouteTableUpdate(_) => return None,
+        };
+
+        match route {
+            Some(_) => self.stats.routed += 1,
+            None => self.stats.orphaned += 1,
+        }
+
+        route
+    }
+
+    /// Forgets all the routes through `peer`, to call when its connection closes.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.pings.remove_peer(peer);
+        self.queries.remove_peer(peer);
+        self.query_hits.remove_peer(peer);
+    }
+
+    pub fn stats(&self) -> RouterStats {
+        self.stats
+    }
+
+    /// Number of routes dropped to stay within [RouterConfig::max_entries].
+    pub fn evicted(&self) -> u64 {
+        self.pings.evicted() + self.queries.evicted() + self.query_hits.evicted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Router, RouterStats};
+    use crate::message::{Message, Payload, Ping, Pong, Push, QueryHit};
+    use crate::routing::Route;
+    use crate::servent::PeerId;
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4},
+        time::Instant,
+    };
+    use uuid::Uuid;
+
+    #[test]
+    fn test_router_route_reply() {
+        let now = Instant::now();
+        let mut router = Router::default();
+        let addr = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6346);
+        let servent_id = Uuid::new_v4();
+
+        let ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+        router.record(&ping, Route::Peer(PeerId(1)), now);
+
+        let pong = Message::with_id(
+            ping.header.descriptor_id,
+            Payload::Pong(Pong {
+                addr,
+                shared_files: 0,
+                shared_kbytes: 0,
+                extensions: vec![],
+            }),
+        );
+        assert_eq!(router.route_reply(&pong, now), Some(Route::Peer(PeerId(1))));
+        assert_eq!(router.route_reply(&ping, now), None);
+
+        let query_hit = Message::new(Payload::QueryHit(QueryHit {
+            addr,
+            speed: 0,
+            results: vec![],
+            trailer: None,
+            servent_id,
+        }));
+        router.record(&query_hit, Route::Peer(PeerId(2)), now);
+
+        let push = Message::new(Payload::Push(Push {
+            servent_id,
+            file_index: 0,
+            addr,
+            extensions: vec![],
+        }));
+        assert_eq!(router.route_reply(&push, now), Some(Route::Peer(PeerId(2))));
+
+        // The connection the QueryHit came from closed.
+        router.remove_peer(PeerId(2));
+        assert_eq!(router.route_reply(&push, now), None);
+
+        assert_eq!(
+            router.stats(),
+            RouterStats {
+                routed: 2,
+                orphaned: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::servent::PeerId;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Where replies to a descriptor go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Route {
+    /// We originated the descriptor, so replies are ours.
+    Local,
+    /// The descriptor came from this connection.
+    Peer(PeerId),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    route: Route,
+    inserted: Instant,
+}
+
+/// Maps GUIDs to the [Route] they came from, forgetting them after
+/// `expiry` and dropping the oldest ones beyond `max_entries`.
+#[derive(Debug)]
+pub struct RoutingTable {
+    entries: HashMap<Uuid, Entry>,
+    /// GUIDs in insertion order, to find the oldest ones. May hold
+    /// GUIDs that have since been removed, which are skipped.
+    order: VecDeque<(Instant, Uuid)>,
+    expiry: Duration,
+    max_entries: usize,
+    /// Entries dropped for lack of room before they expired.
+    evicted: u64,
+}
+
+impl RoutingTable {
+    pub fn new(expiry: Duration, max_entries: usize) -> RoutingTable {
+        RoutingTable {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            expiry,
+            max_entries,
+            evicted: 0,
+        }
+    }
+
+    /// Records that `guid` came from `route`.
+    ///
+    /// An unexpired route is kept as it is, as the first copy of a
+    /// descriptor to arrive came over the shortest path.
+    pub fn insert(&mut self, guid: Uuid, route: Route, now: Instant) {
+        self.purge(now);
+
+        if self.entries.contains_key(&guid) || self.max_entries == 0 {
+            return;
+        }
+
+        while self.entries.len() >= self.max_entries {
+            match self.order.pop_front() {
+                Some((inserted, oldest)) => {
+                    if self.remove_if_inserted(oldest, inserted) {
+                        self.evicted += 1;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        self.entries.insert(
+            guid,
+            Entry {
+                route,
+                inserted: now,
+            },
+        );
+        self.order.push_back((now, guid));
+    }
+
+    /// Returns the route of `guid` if it is known and hasn't expired.
+    pub fn get(&mut self, guid: &Uuid, now: Instant) -> Option<Route> {
+        self.purge(now);
+        self.entries.get(guid).map(|entry| entry.route)
+    }
+
+    /// Forgets all the routes through `peer`, e.g., when its connection closes.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.entries
+            .retain(|_, entry| entry.route != Route::Peer(peer));
+        self.compact();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of entries dropped to stay within `max_entries`.
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    /// Removes `guid` if its entry is the one inserted at `inserted`.
+    fn remove_if_inserted(&mut self, guid: Uuid, inserted: Instant) -> bool {
+        match self.entries.get(&guid) {
+            Some(entry) if entry.inserted == inserted => {
+                self.entries.remove(&guid);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the entries older than `expiry`.
+    fn purge(&mut self, now: Instant) {
+        while let Some(&(inserted, guid)) = self.order.front() {
+            if now.saturating_duration_since(inserted) < self.expiry {
+                break;
+            }
+
+            self.order.pop_front();
+            self.remove_if_inserted(guid, inserted);
+        }
+    }
+
+    /// Drops GUIDs that were removed from `order`, so that it
+    /// doesn't grow with connections coming and going.
+    fn compact(&mut self) {
+        let entries = &self.entries;
+        self.order.retain(|(inserted, guid)| {
+            matches!(entries.get(guid), Some(entry) if entry.inserted == *inserted)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Route, RoutingTable};
+    use crate::servent::PeerId;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_routing_table_expiry() {
+        let start = Instant::now();
+        let mut table = RoutingTable::new(Duration::from_secs(10), 10);
+        let guid = Uuid::new_v4();
+
+        table.insert(guid, Route::Peer(PeerId(1)), start);
+        table.insert(guid, Route::Peer(PeerId(2)), start + Duration::from_secs(1));
+
+        assert_eq!(
+            table.get(&guid, start + Duration::from_secs(9)),
+            Some(Route::Peer(PeerId(1)))
+        );
+        assert_eq!(table.get(&guid, start + Duration::from_secs(10)), None);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_routing_table_bounds() {
+        let now = Instant::now();
+        let mut table = RoutingTable::new(Duration::from_secs(10), 2);
+        let guids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+
+        for (index, guid) in guids.iter().enumerate() {
+            table.insert(*guid, Route::Peer(PeerId(index as u64)), now);
+        }
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.evicted(), 1);
+        assert_eq!(table.get(&guids[0], now), None);
+
+        table.remove_peer(PeerId(1));
+
+        assert_eq!(table.get(&guids[1], now), None);
+        assert_eq!(table.get(&guids[2], now), Some(Route::Peer(PeerId(2))));
+    }
+}
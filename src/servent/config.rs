@@ -0,0 +1,56 @@
+use crate::codec::DEFAULT_QUEUE_CAPACITY;
+use crate::handshake::HandshakeConfig;
+use crate::message::MAX_PAYLOAD_LEN;
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+
+/// Port servents listen on by default.
+pub const DEFAULT_PORT: u16 = 6346;
+
+/// Bounds on the number of connections to one kind of peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slots {
+    /// Number of connections to keep, see [Servent::wants_more](super::Servent::wants_more).
+    pub min: usize,
+    /// Connections over this number are refused.
+    pub max: usize,
+}
+
+impl Slots {
+    pub fn new(min: usize, max: usize) -> Slots {
+        Slots { min, max }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServentConfig {
+    pub listen_addr: SocketAddr,
+    /// Our side of the handshake. [HandshakeConfig::listen_addr] is
+    /// filled in with the address actually listened on if not set.
+    /// [HandshakeConfig::deflate] is ignored, compression isn't supported.
+    pub handshake: HandshakeConfig,
+    pub ultrapeer_slots: Slots,
+    pub leaf_slots: Slots,
+    /// Time allowed for the handshake, after which the connection is dropped.
+    pub handshake_timeout: Duration,
+    /// Descriptors queued for a peer before new ones are dropped.
+    pub queue_capacity: usize,
+    /// Payloads over this size close the connection.
+    pub max_payload_len: u32,
+}
+
+impl Default for ServentConfig {
+    fn default() -> ServentConfig {
+        ServentConfig {
+            listen_addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, DEFAULT_PORT)),
+            handshake: HandshakeConfig::default(),
+            ultrapeer_slots: Slots::new(3, 32),
+            leaf_slots: Slots::new(0, 0),
+            handshake_timeout: Duration::from_secs(10),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            max_payload_len: MAX_PAYLOAD_LEN,
+        }
+    }
+}
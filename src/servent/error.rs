@@ -0,0 +1,39 @@
+use super::PeerId;
+use crate::handshake::{self, Status};
+use snafu::Snafu;
+use std::{io, net::SocketAddr};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Servent I/O failed: {}", source))]
+    Io { source: io::Error },
+    #[snafu(display("Handshake failed: {}", source))]
+    Handshake { source: handshake::Error },
+    /// One of the sides refused the connection, possibly
+    /// suggesting other servents to try.
+    #[snafu(display("Connection rejected with {} {}", status.code, status.reason))]
+    Rejected {
+        status: Status,
+        x_try: Vec<SocketAddr>,
+    },
+    /// All the slots for this kind of peer are taken.
+    #[snafu(display("No free slot for another {:?} connection", kind))]
+    NoSlot { kind: super::PeerKind },
+    #[snafu(display("No connection to peer {}", peer))]
+    UnknownPeer { peer: PeerId },
+    /// The outgoing queue of the peer is full, so the descriptor was dropped.
+    #[snafu(display("Outgoing queue of peer {} is full", peer))]
+    QueueFull { peer: PeerId },
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Error {
+        Error::Io { source }
+    }
+}
+
+impl From<handshake::Error> for Error {
+    fn from(source: handshake::Error) -> Error {
+        Error::Handshake { source }
+    }
+}
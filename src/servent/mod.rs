@@ -0,0 +1,13 @@
+//! A servent, i.e., a node of the network, listening for connections,
+//! dialing others and keeping the ones that completed the handshake.
+
+mod config;
+mod error;
+mod peer;
+#[allow(clippy::module_inception)]
+mod servent;
+
+pub use config::{ServentConfig, Slots, DEFAULT_PORT};
+pub use error::Error;
+pub use peer::{Direction, PeerId, PeerInfo, PeerKind};
+pub use servent::{Event, Servent};
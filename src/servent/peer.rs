@@ -0,0 +1,49 @@
+use crate::handshake::{Headers, Negotiated};
+use std::{fmt, net::SocketAddr};
+
+/// Identifies a connection for as long as the servent runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerId(pub u64);
+
+impl fmt::Display for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Kind of servent on the other side of a connection, from its `X-Ultrapeer`.
+///
+/// Servents that don't send `X-Ultrapeer` predate leaves
+/// and count as ultrapeers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PeerKind {
+    Ultrapeer,
+    Leaf,
+}
+
+impl PeerKind {
+    /// Returns the kind of the servent that sent `headers`.
+    pub fn from_headers(headers: &Headers) -> PeerKind {
+        match headers.get("X-Ultrapeer") {
+            Some(value) if value.trim().eq_ignore_ascii_case("false") => PeerKind::Leaf,
+            _ => PeerKind::Ultrapeer,
+        }
+    }
+}
+
+/// Which side opened the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+/// A live connection to a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub id: PeerId,
+    pub addr: SocketAddr,
+    pub direction: Direction,
+    pub kind: PeerKind,
+    pub negotiated: Negotiated,
+}
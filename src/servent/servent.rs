@@ -0,0 +1,611 @@
+use super::{Direction, Error, PeerId, PeerInfo, PeerKind, ServentConfig, Slots};
+use crate::codec::{self, MessageDecoder, MessageReader, MessageWriter};
+use crate::handshake::{self, Handshake, HandshakeConfig, Outcome, Status};
+use crate::message::Message;
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, SyncSender, TrySendError},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Maximum number of addresses sent in `X-Try` when refusing a connection.
+const MAX_X_TRY: usize = 10;
+
+/// Something that happened to the connections of a [Servent].
+#[derive(Debug)]
+pub enum Event {
+    /// A handshake completed, in either direction.
+    Connected(PeerInfo),
+    Message {
+        peer: PeerId,
+        message: Message,
+    },
+    /// The connection was closed, cleanly if `error` is `None`.
+    Disconnected {
+        peer: PeerId,
+        error: Option<codec::Error>,
+    },
+}
+
+struct PeerEntry {
+    info: PeerInfo,
+    /// Kept to shut the connection down from outside its threads.
+    stream: TcpStream,
+    sender: SyncSender<Message>,
+}
+
+/// Connections along with the slots they take. A slot is reserved
+/// as soon as a handshake is about to succeed, before the connection
+/// is registered, so that concurrent handshakes can't overfill it.
+#[derive(Default)]
+struct Peers {
+    next_id: u64,
+    entries: HashMap<PeerId, PeerEntry>,
+    ultrapeers: usize,
+    leaves: usize,
+}
+
+impl Peers {
+    fn count_mut(&mut self, kind: PeerKind) -> &mut usize {
+        match kind {
+            PeerKind::Ultrapeer => &mut self.ultrapeers,
+            PeerKind::Leaf => &mut self.leaves,
+        }
+    }
+}
+
+/// State shared by the servent and the threads of its connections.
+struct Shared {
+    config: ServentConfig,
+    peers: Mutex<Peers>,
+    events: Sender<Event>,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    fn peers(&self) -> MutexGuard<'_, Peers> {
+        // A panicking connection thread doesn't leave the map half updated.
+        self.peers.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn slots(&self, kind: PeerKind) -> Slots {
+        match kind {
+            PeerKind::Ultrapeer => self.config.ultrapeer_slots,
+            PeerKind::Leaf => self.config.leaf_slots,
+        }
+    }
+
+    /// Takes a slot for a `kind` connection, returning false if they are all taken.
+    fn reserve(&self, kind: PeerKind) -> bool {
+        let max = self.slots(kind).max;
+        let mut peers = self.peers();
+        let count = peers.count_mut(kind);
+
+        if *count >= max {
+            return false;
+        }
+
+        *count += 1;
+        true
+    }
+
+    fn release(&self, kind: PeerKind) {
+        let mut peers = self.peers();
+        let count = peers.count_mut(kind);
+        *count = count.saturating_sub(1);
+    }
+
+    /// Returns the listening addresses of the peers, to suggest
+    /// to a servent whose connection is refused.
+    fn x_try(&self) -> Vec<SocketAddr> {
+        self.peers()
+            .entries
+            .values()
+            .filter_map(|entry| entry.info.negotiated.peer_listen_addr)
+            .take(MAX_X_TRY)
+            .collect()
+    }
+
+    /// Handshake config for a connection with `addr`.
+    ///
+    /// Deflate is never negotiated, as connections exchange
+    /// descriptors uncompressed.
+    fn handshake_config(&self, addr: SocketAddr) -> HandshakeConfig {
+        HandshakeConfig {
+            remote_ip: Some(addr.ip()),
+            x_try: self.x_try(),
+            deflate: false,
+            ..self.config.handshake.clone()
+        }
+    }
+
+    /// Registers a connection whose handshake succeeded and whose slot is
+    /// reserved, and starts the threads reading and writing its descriptors.
+    ///
+    /// The slot is given back if this fails.
+    fn register(
+        self: &Arc<Shared>,
+        stream: TcpStream,
+        direction: Direction,
+        kind: PeerKind,
+        negotiated: handshake::Negotiated,
+        rest: Vec<u8>,
+    ) -> Result<PeerInfo, Error> {
+        let prepare = || -> io::Result<_> {
+            stream.set_read_timeout(None)?;
+            stream.set_write_timeout(None)?;
+            Ok((
+                stream.peer_addr()?,
+                stream.try_clone()?,
+                stream.try_clone()?,
+            ))
+        };
+
+        let (addr, read_stream, write_stream) = match prepare() {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                self.release(kind);
+                return Err(err.into());
+            }
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(self.config.queue_capacity);
+
+        let info = {
+            let mut peers = self.peers();
+            let id = PeerId(peers.next_id);
+            peers.next_id += 1;
+
+            let info = PeerInfo {
+                id,
+                addr,
+                direction,
+                kind,
+                negotiated,
+            };
+
+            peers.entries.insert(
+                id,
+                PeerEntry {
+                    info: info.clone(),
+                    stream,
+                    sender,
+                },
+            );
+
+            info
+        };
+
+        let _ = self.events.send(Event::Connected(info.clone()));
+
+        let mut decoder = MessageDecoder::with_max_payload_len(self.config.max_payload_len);
+        decoder.extend(&rest);
+
+        let shared = Arc::clone(self);
+        let id = info.id;
+        let reader = thread::Builder::new()
+            .name(format!("Reader thread {}", id))
+            .spawn(move || {
+                shared.read_messages(id, MessageReader::with_decoder(read_stream, decoder))
+            });
+
+        let shared = Arc::clone(self);
+        let writer = thread::Builder::new()
+            .name(format!("Writer thread {}", id))
+            .spawn(move || shared.write_messages(id, MessageWriter::new(write_stream), receiver));
+
+        // The slot is now given back by removing the connection.
+        if let Err(err) = reader.and(writer) {
+            self.remove(id, None);
+            return Err(err.into());
+        }
+
+        Ok(info)
+    }
+
+    fn read_messages(&self, id: PeerId, mut reader: MessageReader<TcpStream>) {
+        let error = loop {
+            match reader.read_message() {
+                Ok(Some(message)) => {
+                    let _ = self.events.send(Event::Message { peer: id, message });
+                }
+                Ok(None) => break None,
+                Err(err) if err.is_recoverable() => continue,
+                Err(err) => break Some(err),
+            }
+        };
+
+        self.remove(id, error);
+    }
+
+    fn write_messages(
+        &self,
+        id: PeerId,
+        mut writer: MessageWriter<TcpStream>,
+        receiver: Receiver<Message>,
+    ) {
+        // Ends when the entry, and with it the sender, is removed.
+        for message in receiver {
+            if let Err(err) = writer.send(&message) {
+                self.remove(id, Some(err));
+                return;
+            }
+        }
+    }
+
+    /// Closes and forgets the connection `id`, if it is still there.
+    fn remove(&self, id: PeerId, error: Option<codec::Error>) -> bool {
+        let entry = {
+            let mut peers = self.peers();
+            let entry = peers.entries.remove(&id);
+
+            if let Some(ref entry) = entry {
+                let count = peers.count_mut(entry.info.kind);
+                *count = count.saturating_sub(1);
+            }
+
+            entry
+        };
+
+        match entry {
+            Some(entry) => {
+                let _ = entry.stream.shutdown(Shutdown::Both);
+                let _ = self.events.send(Event::Disconnected { peer: id, error });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Performs the accepting side of the handshake with `stream`
+    /// and registers the connection.
+    fn accept(self: &Arc<Shared>, mut stream: TcpStream) -> Result<PeerInfo, Error> {
+        let addr = stream.peer_addr()?;
+        stream.set_read_timeout(Some(self.config.handshake_timeout))?;
+        stream.set_write_timeout(Some(self.config.handshake_timeout))?;
+
+        let mut reserved = None;
+        let handshake = Handshake::respond(self.handshake_config(addr));
+        let res = handshake::run(handshake, &mut stream, |request| {
+            let kind = PeerKind::from_headers(&request.headers);
+
+            if self.reserve(kind) {
+                reserved = Some(kind);
+                Ok(())
+            } else {
+                Err(Status::busy())
+            }
+        });
+
+        match res {
+            Ok((Outcome::Connected(negotiated), rest)) => match reserved {
+                Some(kind) => self.register(stream, Direction::Incoming, kind, negotiated, rest),
+                None => Err(handshake::Error::unexpected("connected without a decision").into()),
+            },
+            Ok((Outcome::Rejected { status, x_try }, _)) => {
+                if let Some(kind) = reserved {
+                    self.release(kind);
+                }
+
+                Err(Error::Rejected { status, x_try })
+            }
+            Err(err) => {
+                if let Some(kind) = reserved {
+                    self.release(kind);
+                }
+
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Accepts connections on `listener` until the servent shuts down.
+    fn listen(self: Arc<Shared>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let shared = Arc::clone(&self);
+
+            // A failed handshake only concerns that connection.
+            let _ = thread::Builder::new()
+                .name("Handshake thread".to_string())
+                .spawn(move || shared.accept(stream));
+        }
+    }
+}
+
+/// A servent listening for connections and keeping a set of live ones,
+/// each with a thread reading and a thread writing its descriptors.
+///
+/// Received descriptors and changes to the connections are reported
+/// through [Servent::events].
+pub struct Servent {
+    shared: Arc<Shared>,
+    local_addr: SocketAddr,
+    events: Receiver<Event>,
+    listener_thread: Option<JoinHandle<()>>,
+}
+
+impl Servent {
+    /// Starts listening on [ServentConfig::listen_addr].
+    pub fn start(mut config: ServentConfig) -> Result<Servent, Error> {
+        let listener = TcpListener::bind(config.listen_addr)?;
+        let local_addr = listener.local_addr()?;
+
+        if config.handshake.listen_addr.is_none() && !local_addr.ip().is_unspecified() {
+            config.handshake.listen_addr = Some(local_addr);
+        }
+
+        let (sender, events) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            config,
+            peers: Mutex::new(Peers::default()),
+            events: sender,
+            shutdown: AtomicBool::new(false),
+        });
+
+        let listening = Arc::clone(&shared);
+        let listener_thread = thread::Builder::new()
+            .name("Server thread".to_string())
+            .spawn(move || listening.listen(listener))?;
+
+        Ok(Servent {
+            shared,
+            local_addr,
+            events,
+            listener_thread: Some(listener_thread),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+
+    /// Connects to the servent at `addr` and performs the handshake,
+    /// blocking until the connection is registered or refused.
+    pub fn connect(&self, addr: SocketAddr) -> Result<PeerInfo, Error> {
+        let timeout = self.shared.config.handshake_timeout;
+        let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+
+        let handshake = Handshake::initiate(self.shared.handshake_config(addr));
+
+        match handshake::run(handshake, &mut stream, |_| Ok(()))? {
+            (Outcome::Connected(negotiated), rest) => {
+                let kind = PeerKind::from_headers(&negotiated.headers);
+
+                if !self.shared.reserve(kind) {
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return Err(Error::NoSlot { kind });
+                }
+
+                self.shared
+                    .register(stream, Direction::Outgoing, kind, negotiated, rest)
+            }
+            (Outcome::Rejected { status, x_try }, _) => Err(Error::Rejected { status, x_try }),
+        }
+    }
+
+    /// Returns the live connections.
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<_> = self
+            .shared
+            .peers()
+            .entries
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        peers.sort_by_key(|info| info.id);
+        peers
+    }
+
+    /// Number of slots taken by `kind` connections, including
+    /// the ones still completing their handshake.
+    pub fn connection_count(&self, kind: PeerKind) -> usize {
+        *self.shared.peers().count_mut(kind)
+    }
+
+    /// Returns true if there are fewer `kind` connections than
+    /// the minimum, so more should be dialed.
+    pub fn wants_more(&self, kind: PeerKind) -> bool {
+        self.connection_count(kind) < self.shared.slots(kind).min
+    }
+
+    /// Queues `message` to be sent to `peer`.
+    pub fn send(&self, peer: PeerId, message: Message) -> Result<(), Error> {
+        let sender = match self.shared.peers().entries.get(&peer) {
+            Some(entry) => entry.sender.clone(),
+            None => return Err(Error::UnknownPeer { peer }),
+        };
+
+        match sender.try_send(message) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(Error::QueueFull { peer }),
+            Err(TrySendError::Disconnected(_)) => Err(Error::UnknownPeer { peer }),
+        }
+    }
+
+    /// Queues `message` for every peer but `except`, returning the
+    /// number of peers it was queued for.
+    pub fn broadcast(&self, message: &Message, except: Option<PeerId>) -> usize {
+        let peers: Vec<_> = self
+            .shared
+            .peers()
+            .entries
+            .values()
+            .filter(|entry| Some(entry.info.id) != except)
+            .map(|entry| (entry.info.id, entry.sender.clone()))
+            .collect();
+
+        peers
+            .into_iter()
+            .filter(|(_, sender)| sender.try_send(message.clone()).is_ok())
+            .count()
+    }
+
+    /// Closes the connection to `peer`.
+    pub fn disconnect(&self, peer: PeerId) -> Result<(), Error> {
+        if self.shared.remove(peer, None) {
+            Ok(())
+        } else {
+            Err(Error::UnknownPeer { peer })
+        }
+    }
+
+    /// Stops listening and closes all the connections.
+    pub fn shutdown(&mut self) {
+        let listener_thread = match self.listener_thread.take() {
+            Some(listener_thread) => listener_thread,
+            None => return,
+        };
+
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+
+        // Wake the listener up so that it sees the flag.
+        let wake_addr = match self.local_addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => {
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), self.local_addr.port())
+            }
+            IpAddr::V6(ip) if ip.is_unspecified() => {
+                SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), self.local_addr.port())
+            }
+            _ => self.local_addr,
+        };
+
+        if TcpStream::connect(wake_addr).is_ok() {
+            let _ = listener_thread.join();
+        }
+
+        let ids: Vec<_> = self.shared.peers().entries.keys().copied().collect();
+
+        for id in ids {
+            self.shared.remove(id, None);
+        }
+    }
+}
+
+impl Drop for Servent {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Servent};
+    use crate::handshake::{HandshakeConfig, Status};
+    use crate::message::{Message, Payload, Ping};
+    use crate::servent::{Direction, Error, PeerKind, ServentConfig, Slots};
+    use std::time::Duration;
+
+    fn start(ultrapeer_slots: Slots) -> Servent {
+        let config = ServentConfig {
+            listen_addr: ([127, 0, 0, 1], 0).into(),
+            handshake: HandshakeConfig {
+                ultrapeer: true,
+                // Ignored, as compression isn't supported.
+                deflate: true,
+                ..HandshakeConfig::default()
+            },
+            ultrapeer_slots,
+            ..ServentConfig::default()
+        };
+
+        match Servent::start(config) {
+            Ok(servent) => servent,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn next_event(servent: &Servent) -> Event {
+        match servent.events().recv_timeout(Duration::from_secs(5)) {
+            Ok(event) => event,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_servent_loopback() {
+        let mut a = start(Slots::new(0, 1));
+        let b = start(Slots::new(1, 1));
+
+        assert!(b.wants_more(PeerKind::Ultrapeer));
+
+        let a_info = match b.connect(a.local_addr()) {
+            Ok(info) => info,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(a_info.direction, Direction::Outgoing);
+        assert_eq!(a_info.kind, PeerKind::Ultrapeer);
+        assert!(!a_info.negotiated.deflate_incoming && !a_info.negotiated.deflate_outgoing);
+        assert!(!b.wants_more(PeerKind::Ultrapeer));
+
+        let b_id = match next_event(&a) {
+            Event::Connected(info) => {
+                assert_eq!(info.direction, Direction::Incoming);
+                assert_eq!(info.negotiated.peer_listen_addr, Some(b.local_addr()));
+                info.id
+            }
+            event => panic!("unexpected event {:?}", event),
+        };
+
+        let ping = Message::new(Payload::Ping(Ping { extensions: vec![] }));
+
+        if let Err(err) = b.send(a_info.id, ping.clone()) {
+            panic!("{}", err);
+        }
+
+        match next_event(&a) {
+            Event::Message { peer, message } => {
+                assert_eq!(peer, b_id);
+                assert_eq!(message, ping);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        // All of the ultrapeer slots of `a` are taken.
+        let c = start(Slots::new(0, 1));
+
+        match c.connect(a.local_addr()) {
+            Err(Error::Rejected { status, x_try }) => {
+                assert_eq!(status, Status::busy());
+                assert_eq!(x_try, [b.local_addr()]);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        a.shutdown();
+        assert!(a.peers().is_empty());
+
+        match next_event(&b) {
+            Event::Connected(_) => {}
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        match next_event(&b) {
+            Event::Disconnected { peer, error: None } => assert_eq!(peer, a_info.id),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        assert!(b.peers().is_empty());
+    }
+}
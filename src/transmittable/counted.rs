@@ -0,0 +1,150 @@
+use super::{Deserializable, Error, Serializable};
+use std::{convert::TryFrom, io::Write};
+
+/// Converts the value of a count field to the number of items it counts.
+///
+/// Returns `None` if the value is negative or doesn't fit in a `usize`.
+pub fn count_to_usize<C>(count: C) -> Option<usize>
+where
+    usize: TryFrom<C>,
+{
+    usize::try_from(count).ok()
+}
+
+/// Serializes `items` one after another, checking that there are exactly
+/// `count` of them first.
+///
+/// This is the serializing half of `#[transmittable(count = "field")]`,
+/// where `count` is the value of the count field.
+pub fn serialize_counted<T, W>(items: &[T], count: Option<usize>, w: &mut W) -> Result<(), Error>
+where
+    T: Serializable,
+    W: Write + ?Sized,
+{
+    if count != Some(items.len()) {
+        return Err(Error::serialization_failed(format!(
+            "count field doesn't match the {} items",
+            items.len()
+        )));
+    }
+
+    for (index, item) in items.iter().enumerate() {
+        item.serialize_into(w)
+            .map_err(|e| e.in_field(&index.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the total serialized length of `items`.
+pub fn serialized_len_counted<T: Serializable>(items: &[T]) -> usize {
+    match T::FIXED_SIZE {
+        Some(size) => size * items.len(),
+        None => items.iter().map(Serializable::serialized_len).sum(),
+    }
+}
+
+/// Deserializes `count` items one after another from `data`.
+///
+/// This is the deserializing half of `#[transmittable(count = "field")]`.
+/// `count` comes from the input, so it isn't trusted for preallocation
+/// beyond what `data` could possibly hold. Nor is it trusted with items
+/// that take no input, e.g., `[u8; 0]`, which would otherwise be
+/// deserialized `count` times for free.
+pub fn deserialize_counted<'de, T>(
+    data: &'de [u8],
+    count: Option<usize>,
+) -> Result<(Vec<T>, usize), Error>
+where
+    T: Deserializable<'de>,
+{
+    let count = count.ok_or_else(|| Error::invalid_value("count field is out of range"))?;
+
+    let mut items = Vec::with_capacity(count.min(data.len()));
+    let mut start = 0;
+
+    for index in 0..count {
+        let (item, bytes_parsed) =
+            T::deserialize(&data[start..]).map_err(|e| e.within(&index.to_string(), start))?;
+        items.push(item);
+        start += bytes_parsed;
+
+        if bytes_parsed == 0 && index + 1 < count {
+            return Err(Error::invalid_value(format!(
+                "{} more items that take no input",
+                count - index - 1
+            ))
+            .within(&(index + 1).to_string(), start));
+        }
+    }
+
+    Ok((items, start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_to_usize, deserialize_counted, serialize_counted, serialized_len_counted};
+    use crate::transmittable::Error;
+
+    #[test]
+    fn test_counted_round_trip() {
+        let items = [0x0102_u16, 0x0304];
+        let mut data = Vec::new();
+
+        if let Err(err) = serialize_counted(&items, count_to_usize(2_u8), &mut data) {
+            panic!("{}", err);
+        }
+
+        assert_eq!(data, [2, 1, 4, 3]);
+        assert_eq!(serialized_len_counted(&items), 4);
+
+        match deserialize_counted::<u16>(&data, Some(2)) {
+            Ok((deserialized, bytes_parsed)) => {
+                assert_eq!(deserialized, items);
+                assert_eq!(bytes_parsed, 4);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_counted_mismatch() {
+        match serialize_counted(&[1_u8, 2, 3], Some(2), &mut Vec::new()) {
+            Err(Error::SerializationFailed { .. }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert_eq!(count_to_usize(-1_i8), None);
+    }
+
+    #[test]
+    fn test_counted_zero_sized() {
+        match deserialize_counted::<[u8; 0]>(&[0xab], Some(u32::MAX as usize)) {
+            Err(err) => {
+                assert_eq!(err.field().as_str(), "1");
+                assert_eq!(err.offset(), Some(0));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        match deserialize_counted::<[u8; 0]>(&[], Some(1)) {
+            Ok((items, bytes_parsed)) => {
+                assert_eq!(items.len(), 1);
+                assert_eq!(bytes_parsed, 0);
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_counted_eof() {
+        match deserialize_counted::<u16>(&[1, 0, 2], Some(2)) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "1");
+                assert_eq!(err.offset(), Some(2));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
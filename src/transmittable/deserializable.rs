@@ -1,10 +1,31 @@
-pub trait Deserializable {
+use super::Error;
+
+/// The lifetime `'de` is the lifetime of the input data, which lets
+/// the deserialized object borrow from it (e.g., `&'de str` or `&'de [u8]`)
+/// instead of copying it. Types that own all their data implement this trait
+/// for every `'de`, see [DeserializableOwned].
+pub trait Deserializable<'de> {
     /// Input data can be variable length. Implementer has to verify the length.
     /// If the bytes are not sufficient to construct the object, error should be returned.
     /// Otherwise if the bytes >= required number of bytes, the object should be constructed
     /// with initial bytes that are required and upon success deserialized object should bytes
     /// returned along with number of bytes parsed.
-    fn deserialize(data: &[u8]) -> Result<(Self, usize), Box<dyn std::error::Error>>
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error>
     where
         Self: Sized;
 }
+
+/// Implemented for all types that can be deserialized without borrowing from
+/// the input, i.e., implement [Deserializable] for any lifetime.
+pub trait DeserializableOwned: for<'de> Deserializable<'de> {}
+
+impl<T> DeserializableOwned for T where T: for<'de> Deserializable<'de> {}
+
+/// Deserializes a value of type `T` with an encoding chosen by the implementer
+/// instead of the one provided by `T`'s own [Deserializable] impl.
+///
+/// Counterpart of [SerializableAs](super::SerializableAs).
+pub trait DeserializableAs<'de, T> {
+    /// Same as [Deserializable::deserialize] but constructs a value of type `T`.
+    fn deserialize_as(data: &'de [u8]) -> Result<(T, usize), Error>;
+}
@@ -0,0 +1,111 @@
+use super::{Deserializable, DeserializableAs, Error, Serializable, SerializableAs, Transmittable};
+use std::io::Write;
+
+macro_rules! impl_endian_wrapper {
+    ($($wrapper: ident),*) => {$(
+
+        impl<T> Serializable for $wrapper<T>
+        where
+            $wrapper<T>: SerializableAs<T>,
+        {
+            const FIXED_SIZE: Option<usize> = <Self as SerializableAs<T>>::FIXED_SIZE;
+
+            fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                <Self as SerializableAs<T>>::serialize_as_into(&self.0, w)
+            }
+
+            fn serialized_len(&self) -> usize {
+                <Self as SerializableAs<T>>::serialized_len_as(&self.0)
+            }
+        }
+
+        impl<'de, T> Deserializable<'de> for $wrapper<T>
+        where
+            $wrapper<T>: DeserializableAs<'de, T>,
+        {
+            fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+                let (value, bytes_parsed) = <Self as DeserializableAs<'de, T>>::deserialize_as(data)?;
+                Ok(($wrapper(value), bytes_parsed))
+            }
+        }
+
+        impl<T> Transmittable for $wrapper<T>
+        where
+            $wrapper<T>: SerializableAs<T> + for<'de> DeserializableAs<'de, T>,
+        {
+        }
+    )*};
+}
+
+/// Transmits the wrapped value in big endian (network) byte order,
+/// regardless of the byte order used by its own [Transmittable] impl.
+///
+/// Gnutella uses big endian for IP addresses in Pong and QueryHit and
+/// for some GGEP values.
+///
+/// Instead of wrapping, a field of a struct deriving `Transmittable` can
+/// be annotated with `#[transmittable(endian = "be")]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BigEndian<T>(pub T);
+
+/// Transmits the wrapped value in little endian byte order,
+/// regardless of the byte order used by its own [Transmittable] impl.
+///
+/// Instead of wrapping, a field of a struct deriving `Transmittable` can
+/// be annotated with `#[transmittable(endian = "le")]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LittleEndian<T>(pub T);
+
+impl_endian_wrapper!(BigEndian, LittleEndian);
+
+#[cfg(test)]
+mod tests {
+    use super::{BigEndian, LittleEndian};
+    use crate::transmittable::{Deserializable, Serializable};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_big_endian_transmittable() {
+        let x = BigEndian(0x1234_u16);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [0x12, 0x34]);
+
+        let x_deserialized = match <BigEndian<u16> as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 2);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+
+    #[test]
+    fn test_little_endian_transmittable() {
+        let x = LittleEndian(Ipv4Addr::new(1, 2, 3, 4));
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [4, 3, 2, 1]);
+
+        let x_deserialized =
+            match <LittleEndian<Ipv4Addr> as Deserializable>::deserialize(&x_serialized) {
+                Ok((x, bytes_parsed)) => {
+                    assert_eq!(bytes_parsed, 4);
+                    x
+                }
+                Err(err) => panic!("{}", err),
+            };
+
+        assert_eq!(x_deserialized, x);
+    }
+}
@@ -1,9 +1,213 @@
 use snafu::Snafu;
+use std::{fmt, io};
+
+/// Dotted path of the field that failed to (de)serialize, e.g., `header.ttl`.
+///
+/// Empty if the failure happened at the top level.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FieldPath(String);
+
+impl FieldPath {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn push_front(&mut self, field: &str) {
+        if self.0.is_empty() {
+            self.0 = field.to_string();
+        } else {
+            self.0 = format!("{}.{}", field, self.0);
+        }
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("<root>")
+        } else {
+            f.write_str(&self.0)
+        }
+    }
+}
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("Failed to serialize the input: {}", reason))]
-    SerializationFailed { reason: String },
-    #[snafu(display("Failed to deserialize the input: {}", reason))]
-    DeserializationFailed { reason: String },
+    /// The input ended before the value could be constructed.
+    /// `needed` and `available` are counted from `offset`.
+    #[snafu(display(
+        "Failed to deserialize {}: needed {} bytes at offset {} but only {} are available",
+        field,
+        needed,
+        offset,
+        available
+    ))]
+    UnexpectedEof {
+        field: FieldPath,
+        needed: usize,
+        available: usize,
+        offset: usize,
+    },
+    /// The input bytes starting at `offset` don't form a valid value.
+    #[snafu(display(
+        "Failed to deserialize {}: invalid value at offset {}: {}",
+        field,
+        offset,
+        reason
+    ))]
+    InvalidValue {
+        field: FieldPath,
+        offset: usize,
+        reason: String,
+    },
+    /// The value can't be represented on the wire.
+    #[snafu(display("Failed to serialize {}: {}", field, reason))]
+    SerializationFailed { field: FieldPath, reason: String },
+    /// Writing the serialized bytes failed.
+    #[snafu(display("Failed to write serialized {}: {}", field, source))]
+    Io { field: FieldPath, source: io::Error },
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Error {
+        Error::Io {
+            field: FieldPath::default(),
+            source,
+        }
+    }
+}
+
+impl Error {
+    pub fn unexpected_eof(needed: usize, available: usize) -> Error {
+        Error::UnexpectedEof {
+            field: FieldPath::default(),
+            needed,
+            available,
+            offset: 0,
+        }
+    }
+
+    pub fn invalid_value(reason: impl Into<String>) -> Error {
+        Error::InvalidValue {
+            field: FieldPath::default(),
+            offset: 0,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn serialization_failed(reason: impl Into<String>) -> Error {
+        Error::SerializationFailed {
+            field: FieldPath::default(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Records that the error occurred inside `field`.
+    ///
+    /// Used when serializing, where there is no input offset to track.
+    pub fn in_field(mut self, name: &str) -> Error {
+        match self {
+            Error::UnexpectedEof { ref mut field, .. }
+            | Error::InvalidValue { ref mut field, .. }
+            | Error::SerializationFailed { ref mut field, .. }
+            | Error::Io { ref mut field, .. } => field.push_front(name),
+        }
+        self
+    }
+
+    /// Records that the error occurred while deserializing `field`,
+    /// which starts `start` bytes into the input of the caller.
+    ///
+    /// Applied at every level of nesting, this makes the offset
+    /// reported by the error absolute.
+    pub fn within(self, name: &str, start: usize) -> Error {
+        self.in_field(name).at_offset(start)
+    }
+
+    /// Moves the offset reported by the error `start` bytes further,
+    /// for errors found in a part of the input without a field of its own.
+    pub fn at_offset(self, start: usize) -> Error {
+        let mut error = self;
+        match error {
+            Error::UnexpectedEof { ref mut offset, .. }
+            | Error::InvalidValue { ref mut offset, .. } => *offset += start,
+            Error::SerializationFailed { .. } | Error::Io { .. } => {}
+        }
+        error
+    }
+
+    /// Path of the field that failed.
+    pub fn field(&self) -> &FieldPath {
+        match self {
+            Error::UnexpectedEof { field, .. }
+            | Error::InvalidValue { field, .. }
+            | Error::SerializationFailed { field, .. }
+            | Error::Io { field, .. } => field,
+        }
+    }
+
+    /// Offset in the input at which the failing value starts, if deserializing.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::UnexpectedEof { offset, .. } | Error::InvalidValue { offset, .. } => {
+                Some(*offset)
+            }
+            Error::SerializationFailed { .. } | Error::Io { .. } => None,
+        }
+    }
+
+    /// Returns true if the error is only due to the input being too short,
+    /// i.e., reading more bytes (say from a socket) and retrying may succeed.
+    /// Any other error means the input is malformed.
+    pub fn is_eof(&self) -> bool {
+        matches!(self, Error::UnexpectedEof { .. })
+    }
+}
+
+/// Returns [Error::UnexpectedEof] if `data` is shorter than `needed` bytes.
+pub fn ensure_len(data: &[u8], needed: usize) -> Result<(), Error> {
+    if data.len() >= needed {
+        Ok(())
+    } else {
+        Err(Error::unexpected_eof(needed, data.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ensure_len, Error};
+
+    #[test]
+    fn test_error_within() {
+        let err = match ensure_len(&[1, 2], 4) {
+            Ok(()) => panic!("ensure_len should fail"),
+            Err(err) => err,
+        };
+
+        let err = err.within("ttl", 17).within("header", 3);
+
+        assert!(err.is_eof());
+        assert_eq!(err.field().as_str(), "header.ttl");
+        assert_eq!(err.offset(), Some(20));
+
+        match err {
+            Error::UnexpectedEof {
+                needed, available, ..
+            } => assert_eq!((needed, available), (4, 2)),
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn test_error_in_field() {
+        let err = Error::serialization_failed("too long").in_field("name");
+
+        assert!(!err.is_eof());
+        assert_eq!(err.field().as_str(), "name");
+        assert_eq!(err.offset(), None);
+    }
 }
@@ -0,0 +1,456 @@
+use super::{
+    BigEndian, Deserializable, DeserializableAs, Error, Serializable, SerializableAs, Transmittable,
+};
+use std::{convert::TryFrom, io::Write, str};
+
+/// Default `MAX` of [NulTerminated] and [LenPrefixed], i.e., no limit
+/// other than the one imposed by the length prefix type.
+pub const NO_MAX_LEN: usize = usize::MAX;
+
+/// Types that can be the contents of [NulTerminated] and [LenPrefixed],
+/// i.e., a run of bytes.
+pub trait ByteContent {
+    fn content(&self) -> &[u8];
+}
+
+/// Types that can be constructed from the contents of [NulTerminated]
+/// and [LenPrefixed]. String types verify that the bytes are UTF-8.
+pub trait FromByteContent<'de>: Sized {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error>;
+}
+
+/// Raw bytes, transmitted as is without any length or terminator.
+///
+/// Deserializing takes all of the remaining input, so on its own it can only be the
+/// last field of a struct. Inside [LenPrefixed], it takes the prefixed number of bytes.
+/// `&[u8]` is its borrowed counterpart.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Serializable for Bytes {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(&self.0)?;
+        Ok(())
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<'de> Deserializable<'de> for Bytes {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        Ok((Bytes(data.to_vec()), data.len()))
+    }
+}
+
+impl Transmittable for Bytes {}
+
+/// A byte string followed by a NUL byte, like a C string.
+///
+/// Gnutella uses these for the search criteria of a Query and the file
+/// names in a QueryHit. The contents can't contain NUL and can be at most
+/// `MAX` bytes long, not counting the NUL.
+///
+/// Instead of wrapping, a field of a struct deriving `Transmittable` can be
+/// annotated with `#[transmittable(encoding = "nul")]` and optionally
+/// `#[transmittable(max_len = MAX)]`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct NulTerminated<T, const MAX: usize = NO_MAX_LEN>(pub T);
+
+/// A byte string preceded by its length as an `L`, which can be
+/// `u8`, `u16`, `u32` (little endian) or [BigEndian] versions of these.
+///
+/// The contents can be at most `MAX` bytes long.
+///
+/// Instead of wrapping, a field of a struct deriving `Transmittable` can be
+/// annotated with `#[transmittable(encoding = "len_u16")]` (or `"len_u8"`, `"len_u32"`)
+/// and optionally `#[transmittable(max_len = MAX)]` and `#[transmittable(endian = "be")]`,
+/// the latter applying to the length.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct LenPrefixed<L, T, const MAX: usize = NO_MAX_LEN>(pub T, std::marker::PhantomData<L>);
+
+impl<L, T, const MAX: usize> LenPrefixed<L, T, MAX> {
+    pub fn new(value: T) -> Self {
+        LenPrefixed(value, std::marker::PhantomData)
+    }
+}
+
+/// Integer types that can be used as the length in [LenPrefixed].
+pub trait LenPrefix: Serializable + for<'de> Deserializable<'de> + Sized {
+    /// Returns `None` if `len` doesn't fit.
+    fn from_len(len: usize) -> Option<Self>;
+
+    fn to_len(&self) -> usize;
+}
+
+macro_rules! impl_len_prefix {
+    ($($ty: ty),*) => {$(
+        impl LenPrefix for $ty {
+            fn from_len(len: usize) -> Option<Self> {
+                <$ty>::try_from(len).ok()
+            }
+
+            fn to_len(&self) -> usize {
+                *self as usize
+            }
+        }
+
+        impl LenPrefix for BigEndian<$ty> {
+            fn from_len(len: usize) -> Option<Self> {
+                <$ty>::from_len(len).map(BigEndian)
+            }
+
+            fn to_len(&self) -> usize {
+                self.0.to_len()
+            }
+        }
+    )*};
+}
+
+impl_len_prefix!(u8, u16, u32);
+
+impl<T, const MAX: usize> SerializableAs<T> for NulTerminated<T, MAX>
+where
+    T: ByteContent,
+{
+    fn serialize_as_into<W: Write + ?Sized>(value: &T, w: &mut W) -> Result<(), Error> {
+        let content = value.content();
+
+        if content.contains(&0) {
+            return Err(Error::serialization_failed(
+                "NUL-terminated string can't contain NUL",
+            ));
+        }
+        if content.len() > MAX {
+            return Err(Error::serialization_failed(format!(
+                "NUL-terminated string is {} bytes long, at most {} are allowed",
+                content.len(),
+                MAX
+            )));
+        }
+
+        w.write_all(content)?;
+        w.write_all(&[0])?;
+        Ok(())
+    }
+
+    fn serialized_len_as(value: &T) -> usize {
+        value.content().len() + 1
+    }
+}
+
+impl<'de, T, const MAX: usize> DeserializableAs<'de, T> for NulTerminated<T, MAX>
+where
+    T: FromByteContent<'de>,
+{
+    fn deserialize_as(data: &'de [u8]) -> Result<(T, usize), Error> {
+        // Don't look further than the longest allowed string.
+        let searched = &data[..data.len().min(MAX.saturating_add(1))];
+
+        let len = match searched.iter().position(|&byte| byte == 0) {
+            Some(len) => len,
+            None if data.len() > MAX => {
+                return Err(Error::invalid_value(format!(
+                    "NUL-terminated string is longer than {} bytes",
+                    MAX
+                )))
+            }
+            None => return Err(Error::unexpected_eof(data.len() + 1, data.len())),
+        };
+
+        Ok((T::from_content(&data[..len])?, len + 1))
+    }
+}
+
+impl<L, T, const MAX: usize> SerializableAs<T> for LenPrefixed<L, T, MAX>
+where
+    L: LenPrefix,
+    T: ByteContent,
+{
+    fn serialize_as_into<W: Write + ?Sized>(value: &T, w: &mut W) -> Result<(), Error> {
+        let content = value.content();
+
+        let len = match L::from_len(content.len()) {
+            Some(len) if content.len() <= MAX => len,
+            _ => {
+                return Err(Error::serialization_failed(format!(
+                    "length prefixed string is {} bytes long, which is too long",
+                    content.len()
+                )))
+            }
+        };
+
+        len.serialize_into(w)?;
+        w.write_all(content)?;
+        Ok(())
+    }
+
+    fn serialized_len_as(value: &T) -> usize {
+        let len = value.content().len();
+        L::from_len(len).map_or(0, |prefix| prefix.serialized_len()) + len
+    }
+}
+
+impl<'de, L, T, const MAX: usize> DeserializableAs<'de, T> for LenPrefixed<L, T, MAX>
+where
+    L: LenPrefix,
+    T: FromByteContent<'de>,
+{
+    fn deserialize_as(data: &'de [u8]) -> Result<(T, usize), Error> {
+        let (len, start) = L::deserialize(data)?;
+        let len = len.to_len();
+
+        if len > MAX {
+            return Err(Error::invalid_value(format!(
+                "length prefix {} is more than the maximum of {}",
+                len, MAX
+            )));
+        }
+
+        let content = match data[start..].get(..len) {
+            Some(content) => content,
+            None => return Err(Error::unexpected_eof(start + len, data.len())),
+        };
+
+        match T::from_content(content) {
+            Ok(value) => Ok((value, start + len)),
+            Err(err) => Err(err.within("content", start)),
+        }
+    }
+}
+
+macro_rules! impl_framing_wrapper {
+    ($($wrapper: ident<$($param: ident),*>),*) => {$(
+        impl<$($param,)* const MAX: usize> Serializable for $wrapper<$($param,)* MAX>
+        where
+            Self: SerializableAs<T>,
+        {
+            fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                <Self as SerializableAs<T>>::serialize_as_into(&self.0, w)
+            }
+
+            fn serialized_len(&self) -> usize {
+                <Self as SerializableAs<T>>::serialized_len_as(&self.0)
+            }
+        }
+
+        impl<'de, $($param,)* const MAX: usize> Deserializable<'de> for $wrapper<$($param,)* MAX>
+        where
+            Self: DeserializableAs<'de, T>,
+        {
+            fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+                let (value, bytes_parsed) = <Self as DeserializableAs<'de, T>>::deserialize_as(data)?;
+                Ok((Self::from(value), bytes_parsed))
+            }
+        }
+
+        impl<$($param,)* const MAX: usize> Transmittable for $wrapper<$($param,)* MAX>
+        where
+            Self: SerializableAs<T> + for<'de> DeserializableAs<'de, T>,
+        {
+        }
+    )*};
+}
+
+impl_framing_wrapper!(NulTerminated<T>, LenPrefixed<L, T>);
+
+impl<T, const MAX: usize> From<T> for NulTerminated<T, MAX> {
+    fn from(value: T) -> Self {
+        NulTerminated(value)
+    }
+}
+
+impl<L, T, const MAX: usize> From<T> for LenPrefixed<L, T, MAX> {
+    fn from(value: T) -> Self {
+        LenPrefixed::new(value)
+    }
+}
+
+impl ByteContent for String {
+    fn content(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'de> FromByteContent<'de> for String {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error> {
+        <&str>::from_content(content).map(String::from)
+    }
+}
+
+impl ByteContent for &str {
+    fn content(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'de: 'a, 'a> FromByteContent<'de> for &'a str {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error> {
+        str::from_utf8(content)
+            .map_err(|err| Error::invalid_value(format!("string is not valid UTF-8: {}", err)))
+    }
+}
+
+impl ByteContent for Vec<u8> {
+    fn content(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'de> FromByteContent<'de> for Vec<u8> {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error> {
+        Ok(content.to_vec())
+    }
+}
+
+impl ByteContent for &[u8] {
+    fn content(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'de: 'a, 'a> FromByteContent<'de> for &'a [u8] {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error> {
+        Ok(content)
+    }
+}
+
+impl ByteContent for Bytes {
+    fn content(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'de> FromByteContent<'de> for Bytes {
+    fn from_content(content: &'de [u8]) -> Result<Self, Error> {
+        Ok(Bytes(content.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bytes, LenPrefixed, NulTerminated};
+    use crate::transmittable::{BigEndian, Deserializable, Serializable};
+
+    #[test]
+    fn test_nul_terminated_transmittable() {
+        let x = NulTerminated::<String>("metallica".to_string());
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, b"metallica\0");
+        assert_eq!(x.serialized_len(), x_serialized.len());
+
+        let x_deserialized =
+            match <NulTerminated<String> as Deserializable>::deserialize(&x_serialized) {
+                Ok((x, bytes_parsed)) => {
+                    assert_eq!(bytes_parsed, 10);
+                    x
+                }
+                Err(err) => panic!("{}", err),
+            };
+
+        assert_eq!(x_deserialized, x);
+    }
+
+    #[test]
+    fn test_nul_terminated_max_len() {
+        let x = NulTerminated::<String, 4>("metallica".to_string());
+
+        assert!(x.serialize().is_err());
+
+        match <NulTerminated<String, 4> as Deserializable>::deserialize(b"metallica\0") {
+            Ok(x) => panic!("deserialized {:?} longer than max", x),
+            Err(err) => assert!(!err.is_eof()),
+        }
+
+        // Without the NUL in sight and the max not exceeded, more input may help.
+        match <NulTerminated<String, 4> as Deserializable>::deserialize(b"met") {
+            Ok(x) => panic!("deserialized {:?} without NUL", x),
+            Err(err) => assert!(err.is_eof()),
+        }
+
+        assert!(<NulTerminated<String, 4> as Deserializable>::deserialize(b"meta\0").is_ok());
+    }
+
+    #[test]
+    fn test_nul_terminated_invalid_utf8() {
+        match <NulTerminated<String> as Deserializable>::deserialize(b"\xff\0") {
+            Ok(x) => panic!("deserialized invalid UTF-8 {:?}", x),
+            Err(err) => assert!(!err.is_eof()),
+        }
+
+        assert!(<NulTerminated<Vec<u8>> as Deserializable>::deserialize(b"\xff\0").is_ok());
+    }
+
+    #[test]
+    fn test_len_prefixed_transmittable() {
+        let x = LenPrefixed::<BigEndian<u16>, Vec<u8>>::new(vec![1, 2, 3]);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [0, 3, 1, 2, 3]);
+        assert_eq!(x.serialized_len(), x_serialized.len());
+
+        let x_deserialized =
+            match <LenPrefixed<BigEndian<u16>, Vec<u8>> as Deserializable>::deserialize(
+                &x_serialized,
+            ) {
+                Ok((x, bytes_parsed)) => {
+                    assert_eq!(bytes_parsed, 5);
+                    x
+                }
+                Err(err) => panic!("{}", err),
+            };
+
+        assert_eq!(x_deserialized, x);
+    }
+
+    #[test]
+    fn test_len_prefixed_limits() {
+        assert!(LenPrefixed::<u8, Vec<u8>>::new(vec![0; 256])
+            .serialize()
+            .is_err());
+        assert!(LenPrefixed::<u16, &str, 2>::new("abc").serialize().is_err());
+
+        match <LenPrefixed<u16, &str, 2> as Deserializable>::deserialize(&[3, 0, b'a', b'b', b'c'])
+        {
+            Ok(x) => panic!("deserialized {:?} longer than max", x),
+            Err(err) => assert!(!err.is_eof()),
+        }
+
+        match <LenPrefixed<u16, &str> as Deserializable>::deserialize(&[3, 0, b'a']) {
+            Ok(x) => panic!("deserialized {:?} from truncated input", x),
+            Err(err) => assert!(err.is_eof()),
+        }
+    }
+
+    #[test]
+    fn test_bytes_transmittable() {
+        let x = Bytes(vec![0xc3, 0x00, 0x01]);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, x.0);
+
+        let x_deserialized = match <Bytes as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 3);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::transmittable::{Deserializable, Error, Serializable, Transmittable};
+use std::{convert::TryInto, io::Write};
+
+/// Arrays are transmitted as their elements one after another,
+/// e.g., the vendor code of a QHD trailer as `[u8; 4]`.
+impl<T: Serializable, const N: usize> Serializable for [T; N] {
+    const FIXED_SIZE: Option<usize> = match T::FIXED_SIZE {
+        Some(size) => Some(size * N),
+        None => None,
+    };
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        for (index, item) in self.iter().enumerate() {
+            item.serialize_into(w)
+                .map_err(|e| e.in_field(&index.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn serialized_len(&self) -> usize {
+        match Self::FIXED_SIZE {
+            Some(size) => size,
+            None => self.iter().map(Serializable::serialized_len).sum(),
+        }
+    }
+}
+
+impl<'de, T: Deserializable<'de>, const N: usize> Deserializable<'de> for [T; N] {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        let mut items = Vec::with_capacity(N);
+        let mut start = 0;
+
+        for index in 0..N {
+            let (item, bytes_parsed) =
+                T::deserialize(&data[start..]).map_err(|e| e.within(&index.to_string(), start))?;
+            items.push(item);
+            start += bytes_parsed;
+        }
+
+        match items.try_into() {
+            Ok(array) => Ok((array, start)),
+            Err(_) => unreachable!("exactly N items were deserialized"),
+        }
+    }
+}
+
+impl<T: Transmittable, const N: usize> Transmittable for [T; N] {}
+
+#[cfg(test)]
+mod tests {
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_array_transmittable() {
+        let x = [0x0102_u16, 0x0304, 0x0506];
+
+        assert_eq!(<[u16; 3] as Serializable>::FIXED_SIZE, Some(6));
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [2, 1, 4, 3, 6, 5]);
+
+        let x_deserialized = match <[u16; 3] as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 6);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+
+        match <[u16; 3] as Deserializable>::deserialize(&x_serialized[..5]) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "2");
+                assert_eq!(err.offset(), Some(4));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
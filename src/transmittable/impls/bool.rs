@@ -0,0 +1,57 @@
+use crate::transmittable::{ensure_len, Deserializable, Error, Serializable, Transmittable};
+use std::io::Write;
+
+/// `bool` is transmitted as a single byte, 0 or 1.
+/// Any other byte is rejected so that values round-trip.
+impl Serializable for bool {
+    const FIXED_SIZE: Option<usize> = Some(1);
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(&[*self as u8])?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserializable<'de> for bool {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        ensure_len(data, 1)?;
+
+        match data[0] {
+            0 => Ok((false, 1)),
+            1 => Ok((true, 1)),
+            byte => Err(Error::invalid_value(format!(
+                "expected 0 or 1 for bool, found {}",
+                byte
+            ))),
+        }
+    }
+}
+
+impl Transmittable for bool {}
+
+#[cfg(test)]
+mod tests {
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_bool_transmittable() {
+        for &x in &[false, true] {
+            let x_serialized = match x.serialize() {
+                Ok(bytes) => bytes,
+                Err(err) => panic!("{}", err),
+            };
+
+            assert_eq!(x_serialized, [x as u8]);
+
+            match <bool as Deserializable>::deserialize(&x_serialized) {
+                Ok((x_deserialized, bytes_parsed)) => {
+                    assert_eq!(bytes_parsed, 1);
+                    assert_eq!(x_deserialized, x);
+                }
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        assert!(<bool as Deserializable>::deserialize(&[2]).is_err());
+    }
+}
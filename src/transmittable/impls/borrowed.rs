@@ -0,0 +1,107 @@
+use crate::transmittable::{
+    Deserializable, DeserializableAs, Error, NulTerminated, Serializable, SerializableAs,
+};
+use std::io::Write;
+
+/// Byte slices are transmitted as is, without any length or terminator.
+///
+/// Deserializing borrows all of the remaining input, so a byte slice can only
+/// be the last field of a struct, e.g., the trailing GGEP block of a descriptor.
+impl Serializable for &[u8] {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(self)?;
+        Ok(())
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'de: 'a, 'a> Deserializable<'de> for &'a [u8] {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        Ok((data, data.len()))
+    }
+}
+
+/// String slices are transmitted NUL-terminated, as is the case for
+/// Gnutella search criteria and file names. See [NulTerminated].
+impl Serializable for &str {
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        <NulTerminated<&str> as SerializableAs<&str>>::serialize_as_into(self, w)
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.len() + 1
+    }
+}
+
+impl<'de: 'a, 'a> Deserializable<'de> for &'a str {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        <NulTerminated<&'a str> as DeserializableAs<'de, &'a str>>::deserialize_as(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deserializable, Serializable};
+
+    #[test]
+    fn test_bytes_transmittable() {
+        let x: &[u8] = &[0xc3, 0x82, 0x00, 0x01];
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, x);
+
+        let x_deserialized = match <&[u8] as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 4);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+
+    #[test]
+    fn test_str_transmittable() {
+        let x = "metallica";
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, b"metallica\0");
+        assert_eq!(x.serialized_len(), x_serialized.len());
+
+        // Trailing bytes after the NUL are left alone.
+        let mut data = x_serialized.clone();
+        data.push(0x1c);
+
+        let x_deserialized = match <&str as Deserializable>::deserialize(&data) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 10);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+
+    #[test]
+    fn test_str_without_nul() {
+        match <&str as Deserializable>::deserialize(b"metallica") {
+            Ok(x) => panic!("deserialized {:?} without NUL", x),
+            Err(err) => assert!(err.is_eof()),
+        }
+
+        assert!("a\0b".serialize().is_err());
+    }
+}
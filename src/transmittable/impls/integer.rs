@@ -1,5 +1,8 @@
-use crate::transmittable::{Deserializable, Error, Serializable, Transmittable};
-use std::{convert::TryInto, mem::size_of};
+use crate::transmittable::{
+    ensure_len, BigEndian, Deserializable, DeserializableAs, Error, LittleEndian, Serializable,
+    SerializableAs, Transmittable,
+};
+use std::{io::Write, mem::size_of};
 
 macro_rules! impl_integer_transmittable {
     ($($ty: ty),*) => {$(
@@ -8,40 +11,57 @@ macro_rules! impl_integer_transmittable {
         /// Gnutella specification asks everything to be little ending
         /// unless specified explicitly.
         impl Serializable for $ty {
-            fn serialize_append(&self, mut v: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-                v.extend(&self.to_le_bytes());
-                Ok(v)
+            const FIXED_SIZE: Option<usize> = Some(size_of::<$ty>());
+
+            fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                <LittleEndian<$ty> as SerializableAs<$ty>>::serialize_as_into(self, w)
             }
         }
 
         /// All integer types are serialized to little endian.
         /// Gnutella specification asks everything to be little ending
         /// unless specified explicitly.
-        impl Deserializable for $ty {
-            fn deserialize(data: &[u8]) -> Result<(Self, usize), Box<dyn std::error::Error>> {
-                if data.len() >= size_of::<$ty>() {
-                    let data: [u8; size_of::<$ty>()] = data[0..size_of::<$ty>()].try_into()?;
-                    Ok((<$ty>::from_le_bytes(data), data.len()))
-                } else {
-                    Err(Box::new(Error::DeserializationFailed {
-                         reason: format!(
-                            "{} bytes input data is required for constructing {}.\n\
-                             Input array should have length {0} but found data.len() = {}\n\
-                             where data = {:?}", size_of::<$ty>(), stringify!($ty), data.len(), data)
-                    }))
-                }
+        impl<'de> Deserializable<'de> for $ty {
+            fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+                <LittleEndian<$ty> as DeserializableAs<'de, $ty>>::deserialize_as(data)
             }
         }
 
         impl Transmittable for $ty {}
+
+        impl_integer_transmittable_as!($ty, LittleEndian, to_le_bytes, from_le_bytes);
+        impl_integer_transmittable_as!($ty, BigEndian, to_be_bytes, from_be_bytes);
     )*};
 }
 
+macro_rules! impl_integer_transmittable_as {
+    ($ty: ty, $wrapper: ident, $to_bytes: ident, $from_bytes: ident) => {
+        impl SerializableAs<$ty> for $wrapper<$ty> {
+            const FIXED_SIZE: Option<usize> = Some(size_of::<$ty>());
+
+            fn serialize_as_into<W: Write + ?Sized>(value: &$ty, w: &mut W) -> Result<(), Error> {
+                w.write_all(&value.$to_bytes())?;
+                Ok(())
+            }
+        }
+
+        impl<'de> DeserializableAs<'de, $ty> for $wrapper<$ty> {
+            fn deserialize_as(data: &'de [u8]) -> Result<($ty, usize), Error> {
+                ensure_len(data, size_of::<$ty>())?;
+
+                let mut bytes = [0_u8; size_of::<$ty>()];
+                bytes.copy_from_slice(&data[0..size_of::<$ty>()]);
+                Ok((<$ty>::$from_bytes(bytes), bytes.len()))
+            }
+        }
+    };
+}
+
 impl_integer_transmittable!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
 
 #[cfg(test)]
 mod tests {
-    use super::{Deserializable, Serializable};
+    use super::{BigEndian, Deserializable, Serializable};
 
     #[test]
     fn test_integer_transmittable() {
@@ -52,6 +72,8 @@ mod tests {
             Err(err) => panic!("{}", err),
         };
 
+        assert_eq!(<i64 as Serializable>::FIXED_SIZE, Some(x_serialized.len()));
+
         let x_deserialized = match <i64 as Deserializable>::deserialize(&x_serialized) {
             Ok((x, bytes_parsed)) => {
                 assert_eq!(bytes_parsed, std::mem::size_of::<i64>());
@@ -62,4 +84,26 @@ mod tests {
 
         assert_eq!(x_deserialized, x);
     }
+
+    #[test]
+    fn test_integer_big_endian_transmittable() {
+        let x = BigEndian(0x0102_0304_u32);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [1, 2, 3, 4]);
+
+        let x_deserialized = match <BigEndian<u32> as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 4);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
 }
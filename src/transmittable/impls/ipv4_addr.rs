@@ -1,33 +1,63 @@
-use crate::transmittable::{Deserializable, Error, Serializable, Transmittable};
-use std::net::Ipv4Addr;
+use crate::transmittable::{
+    ensure_len, BigEndian, Deserializable, DeserializableAs, Error, LittleEndian, Serializable,
+    SerializableAs, Transmittable,
+};
+use std::{io::Write, net::Ipv4Addr};
 
 impl Serializable for Ipv4Addr {
-    fn serialize_append(&self, mut v: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        v.extend(&self.octets());
-        Ok(v)
+    const FIXED_SIZE: Option<usize> = Some(4);
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(&self.octets())?;
+        Ok(())
     }
 }
 
-impl Deserializable for Ipv4Addr {
-    fn deserialize(data: &[u8]) -> Result<(Self, usize), Box<dyn std::error::Error>> {
-        if data.len() >= 4 {
-            Ok((Ipv4Addr::new(data[0], data[1], data[2], data[3]), 4))
-        } else {
-            Err(Box::new(Error::DeserializationFailed {
-                reason: format!(
-                    "4 bytes input data required for constructing Ipv4Addr.\n\
-                 Input array should have length 4 but found data.len() = {}\n\
-                 where data = {:?}",
-                    data.len(),
-                    data
-                ),
-            }))
-        }
+impl<'de> Deserializable<'de> for Ipv4Addr {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        ensure_len(data, 4)?;
+
+        Ok((Ipv4Addr::new(data[0], data[1], data[2], data[3]), 4))
     }
 }
 
 impl Transmittable for Ipv4Addr {}
 
+/// [Ipv4Addr] is transmitted in big endian (network byte order) by default.
+impl SerializableAs<Ipv4Addr> for BigEndian<Ipv4Addr> {
+    const FIXED_SIZE: Option<usize> = Some(4);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Ipv4Addr, w: &mut W) -> Result<(), Error> {
+        value.serialize_into(w)
+    }
+}
+
+impl<'de> DeserializableAs<'de, Ipv4Addr> for BigEndian<Ipv4Addr> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Ipv4Addr, usize), Error> {
+        Ipv4Addr::deserialize(data)
+    }
+}
+
+impl SerializableAs<Ipv4Addr> for LittleEndian<Ipv4Addr> {
+    const FIXED_SIZE: Option<usize> = Some(4);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Ipv4Addr, w: &mut W) -> Result<(), Error> {
+        let mut octets = value.octets();
+        octets.reverse();
+        w.write_all(&octets)?;
+        Ok(())
+    }
+}
+
+impl<'de> DeserializableAs<'de, Ipv4Addr> for LittleEndian<Ipv4Addr> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Ipv4Addr, usize), Error> {
+        let (addr, bytes_parsed) = Ipv4Addr::deserialize(data)?;
+        let mut octets = addr.octets();
+        octets.reverse();
+        Ok((Ipv4Addr::from(octets), bytes_parsed))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Deserializable, Serializable};
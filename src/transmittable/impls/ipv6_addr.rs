@@ -0,0 +1,88 @@
+use crate::transmittable::{
+    ensure_len, BigEndian, Deserializable, DeserializableAs, Error, LittleEndian, Serializable,
+    SerializableAs, Transmittable,
+};
+use std::{convert::TryInto, io::Write, net::Ipv6Addr};
+
+impl Serializable for Ipv6Addr {
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(&self.octets())?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserializable<'de> for Ipv6Addr {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        ensure_len(data, 16)?;
+
+        let octets: [u8; 16] = data[0..16].try_into().unwrap();
+        Ok((Ipv6Addr::from(octets), 16))
+    }
+}
+
+impl Transmittable for Ipv6Addr {}
+
+/// [Ipv6Addr] is transmitted in big endian (network byte order) by default.
+impl SerializableAs<Ipv6Addr> for BigEndian<Ipv6Addr> {
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Ipv6Addr, w: &mut W) -> Result<(), Error> {
+        value.serialize_into(w)
+    }
+}
+
+impl<'de> DeserializableAs<'de, Ipv6Addr> for BigEndian<Ipv6Addr> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Ipv6Addr, usize), Error> {
+        Ipv6Addr::deserialize(data)
+    }
+}
+
+impl SerializableAs<Ipv6Addr> for LittleEndian<Ipv6Addr> {
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Ipv6Addr, w: &mut W) -> Result<(), Error> {
+        let mut octets = value.octets();
+        octets.reverse();
+        w.write_all(&octets)?;
+        Ok(())
+    }
+}
+
+impl<'de> DeserializableAs<'de, Ipv6Addr> for LittleEndian<Ipv6Addr> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Ipv6Addr, usize), Error> {
+        let (addr, bytes_parsed) = Ipv6Addr::deserialize(data)?;
+        let mut octets = addr.octets();
+        octets.reverse();
+        Ok((Ipv6Addr::from(octets), bytes_parsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deserializable, Serializable};
+    use std::net::Ipv6Addr;
+
+    #[test]
+    fn test_ipv6_addr_transmittable() {
+        let x = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized[..2], [0x20, 0x01]);
+
+        let x_deserialized = match <Ipv6Addr as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 16);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+}
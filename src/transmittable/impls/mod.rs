@@ -1,3 +1,11 @@
+mod array;
+mod bool;
+mod borrowed;
 mod integer;
 mod ipv4_addr;
+mod ipv6_addr;
+mod socket_addr;
+mod tuple;
 mod uuid;
+
+pub use socket_addr::PortFirst;
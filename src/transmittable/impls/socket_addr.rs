@@ -0,0 +1,174 @@
+use crate::transmittable::{
+    Deserializable, DeserializableAs, Error, Serializable, SerializableAs, Transmittable,
+};
+use std::{
+    io::Write,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6},
+};
+
+/// Transmits the wrapped socket address as the port followed by the IP,
+/// instead of the IP followed by the port.
+///
+/// Gnutella mostly puts the IP first (Push, GGEP `IPP`), but the Pong
+/// puts the port first.
+///
+/// Instead of wrapping, a field of a struct deriving `Transmittable` can
+/// be annotated with `#[transmittable(port_first)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortFirst<T>(pub T);
+
+impl<T> Serializable for PortFirst<T>
+where
+    PortFirst<T>: SerializableAs<T>,
+{
+    const FIXED_SIZE: Option<usize> = <Self as SerializableAs<T>>::FIXED_SIZE;
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        <Self as SerializableAs<T>>::serialize_as_into(&self.0, w)
+    }
+}
+
+impl<'de, T> Deserializable<'de> for PortFirst<T>
+where
+    PortFirst<T>: DeserializableAs<'de, T>,
+{
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        let (value, bytes_parsed) = <Self as DeserializableAs<'de, T>>::deserialize_as(data)?;
+        Ok((PortFirst(value), bytes_parsed))
+    }
+}
+
+impl<T> Transmittable for PortFirst<T> where
+    PortFirst<T>: SerializableAs<T> + for<'de> DeserializableAs<'de, T>
+{
+}
+
+/// Socket addresses are transmitted as the IP in big endian followed by
+/// the port in little endian, as in a Push or GGEP `IPP`. IPv6 flow info
+/// and scope ID are not transmitted.
+macro_rules! impl_socket_addr_transmittable {
+    ($($ty: ident($ip: ty, $size: expr, $new: expr)),*) => {$(
+        impl Serializable for $ty {
+            const FIXED_SIZE: Option<usize> = Some($size + 2);
+
+            fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                self.ip().serialize_into(w).map_err(|e| e.in_field("ip"))?;
+                self.port().serialize_into(w).map_err(|e| e.in_field("port"))
+            }
+        }
+
+        impl<'de> Deserializable<'de> for $ty {
+            fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+                let (ip, bytes_parsed) = <$ip>::deserialize(data).map_err(|e| e.within("ip", 0))?;
+                let (port, _) = u16::deserialize(&data[bytes_parsed..])
+                    .map_err(|e| e.within("port", bytes_parsed))?;
+                Ok(($new(ip, port), $size + 2))
+            }
+        }
+
+        impl Transmittable for $ty {}
+
+        impl SerializableAs<$ty> for PortFirst<$ty> {
+            const FIXED_SIZE: Option<usize> = Some($size + 2);
+
+            fn serialize_as_into<W: Write + ?Sized>(value: &$ty, w: &mut W) -> Result<(), Error> {
+                value.port().serialize_into(w).map_err(|e| e.in_field("port"))?;
+                value.ip().serialize_into(w).map_err(|e| e.in_field("ip"))
+            }
+        }
+
+        impl<'de> DeserializableAs<'de, $ty> for PortFirst<$ty> {
+            fn deserialize_as(data: &'de [u8]) -> Result<($ty, usize), Error> {
+                let (port, bytes_parsed) =
+                    u16::deserialize(data).map_err(|e| e.within("port", 0))?;
+                let (ip, _) = <$ip>::deserialize(&data[bytes_parsed..])
+                    .map_err(|e| e.within("ip", bytes_parsed))?;
+                Ok(($new(ip, port), $size + 2))
+            }
+        }
+    )*};
+}
+
+impl_socket_addr_transmittable!(
+    SocketAddrV4(Ipv4Addr, 4, SocketAddrV4::new),
+    SocketAddrV6(Ipv6Addr, 16, |ip, port| SocketAddrV6::new(ip, port, 0, 0))
+);
+
+#[cfg(test)]
+mod tests {
+    use super::PortFirst;
+    use crate::transmittable::{Deserializable, Serializable};
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    #[test]
+    fn test_socket_addr_v4_transmittable() {
+        let x = SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 6346);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [1, 2, 3, 4, 0xca, 0x18]);
+
+        match <SocketAddrV4 as Deserializable>::deserialize(&x_serialized) {
+            Ok((x_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 6);
+                assert_eq!(x_deserialized, x);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        let x_serialized = match PortFirst(x).serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [0xca, 0x18, 1, 2, 3, 4]);
+
+        match <PortFirst<SocketAddrV4> as Deserializable>::deserialize(&x_serialized) {
+            Ok((x_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 6);
+                assert_eq!(x_deserialized, PortFirst(x));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_socket_addr_v6_transmittable() {
+        let x = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 6346, 0, 0);
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized.len(), 18);
+        assert_eq!(x_serialized[16..], [0xca, 0x18]);
+
+        match <SocketAddrV6 as Deserializable>::deserialize(&x_serialized[..17]) {
+            Err(err) => {
+                assert!(err.is_eof());
+                assert_eq!(err.field().as_str(), "port");
+                assert_eq!(err.offset(), Some(16));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        let x_serialized = match PortFirst(x).serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized[..2], [0xca, 0x18]);
+
+        match <PortFirst<SocketAddrV6> as Deserializable>::deserialize(&x_serialized) {
+            Ok((x_deserialized, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 18);
+                assert_eq!(x_deserialized, PortFirst(x));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
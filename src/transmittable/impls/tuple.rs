@@ -0,0 +1,80 @@
+use crate::transmittable::{fixed_size_sum, Deserializable, Error, Serializable, Transmittable};
+use std::io::Write;
+
+/// Tuples are transmitted as their elements one after another,
+/// like a tuple struct deriving `Transmittable`.
+macro_rules! impl_tuple_transmittable {
+    ($(($($param: ident $index: tt),+)),*) => {$(
+        impl<$($param: Serializable),+> Serializable for ($($param,)+) {
+            const FIXED_SIZE: Option<usize> = fixed_size_sum(&[$($param::FIXED_SIZE),+]);
+
+            fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                $(
+                    self.$index
+                        .serialize_into(w)
+                        .map_err(|e| e.in_field(stringify!($index)))?;
+                )+
+                Ok(())
+            }
+
+            fn serialized_len(&self) -> usize {
+                0 $(+ self.$index.serialized_len())+
+            }
+        }
+
+        impl<'de, $($param: Deserializable<'de>),+> Deserializable<'de> for ($($param,)+) {
+            #[allow(non_snake_case)]
+            fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+                let mut start = 0;
+                $(
+                    let ($param, bytes_parsed) = $param::deserialize(&data[start..])
+                        .map_err(|e| e.within(stringify!($index), start))?;
+                    start += bytes_parsed;
+                )+
+                Ok((($($param,)+), start))
+            }
+        }
+
+        impl<$($param: Transmittable),+> Transmittable for ($($param,)+) {}
+    )*};
+}
+
+impl_tuple_transmittable!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::transmittable::{Deserializable, Serializable};
+
+    #[test]
+    fn test_tuple_transmittable() {
+        let x = (1_u8, 0x0203_u16, true);
+
+        assert_eq!(<(u8, u16, bool) as Serializable>::FIXED_SIZE, Some(4));
+
+        let x_serialized = match x.serialize() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_serialized, [1, 3, 2, 1]);
+
+        let x_deserialized = match <(u8, u16, bool) as Deserializable>::deserialize(&x_serialized) {
+            Ok((x, bytes_parsed)) => {
+                assert_eq!(bytes_parsed, 4);
+                x
+            }
+            Err(err) => panic!("{}", err),
+        };
+
+        assert_eq!(x_deserialized, x);
+    }
+}
@@ -1,36 +1,66 @@
-use crate::transmittable::{Deserializable, Error, Serializable, Transmittable};
-use std::convert::TryInto;
+use crate::transmittable::{
+    ensure_len, BigEndian, Deserializable, DeserializableAs, Error, LittleEndian, Serializable,
+    SerializableAs, Transmittable,
+};
+use std::{convert::TryInto, io::Write};
 use uuid::Uuid;
 
 impl Serializable for Uuid {
-    fn serialize_append(&self, mut v: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        v.extend(self.as_bytes().iter().rev());
-        Ok(v)
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        let mut bytes = *self.as_bytes();
+        bytes.reverse(); // gnutella protocol requires uuid in little endian
+        w.write_all(&bytes)?;
+        Ok(())
     }
 }
 
-impl Deserializable for Uuid {
-    fn deserialize(data: &[u8]) -> Result<(Self, usize), Box<dyn std::error::Error>> {
-        if data.len() >= 16 {
-            let mut data: [u8; 16] = data[0..16].try_into()?;
-            data.reverse(); // gnutella protocol requires uuid in little endian
-            Ok((Uuid::from_bytes(data), 16))
-        } else {
-            Err(Box::new(Error::DeserializationFailed {
-                reason: format!(
-                    "16 bytes input data required for constructing uuid::Uuid.\n\
-                 Input array should have length 16 but found data.len() = {}\n\
-                 where data = {:?}",
-                    data.len(),
-                    data
-                ),
-            }))
-        }
+impl<'de> Deserializable<'de> for Uuid {
+    fn deserialize(data: &'de [u8]) -> Result<(Self, usize), Error> {
+        ensure_len(data, 16)?;
+
+        let mut data: [u8; 16] = data[0..16].try_into().unwrap();
+        data.reverse(); // gnutella protocol requires uuid in little endian
+        Ok((Uuid::from_bytes(data), 16))
     }
 }
 
 impl Transmittable for Uuid {}
 
+/// [Uuid] is transmitted in little endian by default.
+impl SerializableAs<Uuid> for LittleEndian<Uuid> {
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Uuid, w: &mut W) -> Result<(), Error> {
+        value.serialize_into(w)
+    }
+}
+
+impl<'de> DeserializableAs<'de, Uuid> for LittleEndian<Uuid> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Uuid, usize), Error> {
+        Uuid::deserialize(data)
+    }
+}
+
+impl SerializableAs<Uuid> for BigEndian<Uuid> {
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn serialize_as_into<W: Write + ?Sized>(value: &Uuid, w: &mut W) -> Result<(), Error> {
+        w.write_all(value.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'de> DeserializableAs<'de, Uuid> for BigEndian<Uuid> {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Uuid, usize), Error> {
+        let (uuid, bytes_parsed) = Uuid::deserialize(data)?;
+        let mut bytes = *uuid.as_bytes();
+        bytes.reverse();
+        Ok((Uuid::from_bytes(bytes), bytes_parsed))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Deserializable, Serializable};
@@ -85,6 +115,8 @@ mod tests {
             Err(err) => panic!("{}", err),
         };
 
+        assert_eq!(<Uuid as Serializable>::FIXED_SIZE, Some(16));
+
         let new_uuid = match <Uuid as Deserializable>::deserialize(&serialized_uuid) {
             Ok((uuid, bytes_parsed)) => {
                 assert_eq!(bytes_parsed, 16);
@@ -1,10 +1,22 @@
+mod counted;
 mod deserializable;
+mod endian;
 mod error;
+mod framing;
 mod impls;
 mod serializable;
+mod trailing;
+#[allow(clippy::module_inception)]
 mod transmittable;
 
-pub use deserializable::Deserializable;
-pub use error::Error;
-pub use serializable::Serializable;
+pub use counted::{count_to_usize, deserialize_counted, serialize_counted, serialized_len_counted};
+pub use deserializable::{Deserializable, DeserializableAs, DeserializableOwned};
+pub use endian::{BigEndian, LittleEndian};
+pub use error::{ensure_len, Error, FieldPath};
+pub use framing::{
+    ByteContent, Bytes, FromByteContent, LenPrefix, LenPrefixed, NulTerminated, NO_MAX_LEN,
+};
+pub use impls::PortFirst;
+pub use serializable::{fixed_size_common, fixed_size_sum, Serializable, SerializableAs};
+pub use trailing::{Optional, Rest};
 pub use transmittable::Transmittable;
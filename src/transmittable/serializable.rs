@@ -1,18 +1,212 @@
-use std::mem::size_of;
+use super::Error;
+use std::io::{self, Write};
 
 pub trait Serializable {
+    /// Number of bytes every value of the type takes when serialized,
+    /// or `None` if it differs between values (e.g. strings).
+    ///
+    /// For fixed-layout types like integers, [Ipv4Addr](std::net::Ipv4Addr)
+    /// and [Uuid](uuid::Uuid), this is known at compile time, which lets
+    /// e.g. the Gnutella descriptor header be parsed without looking at its fields.
+    const FIXED_SIZE: Option<usize> = None;
+
     /// This is to be defined by the implementer such that
-    /// the serialized version of self extends the argument vector `v`.
-    /// In general, the the vector `v` received in the arguments such be returned.
-    fn serialize_append(&self, v: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+    /// the serialized version of self is written to `w`.
+    ///
+    /// `w` can be anything implementing [Write], e.g., a `Vec<u8>`,
+    /// a socket or a compression stream, so no intermediate buffer is needed.
+    fn serialize_into<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error>;
+
+    /// Number of bytes [serialize_into](Serializable::serialize_into) writes for self.
+    ///
+    /// The default implementation returns [FIXED_SIZE](Serializable::FIXED_SIZE)
+    /// if set, and otherwise counts the bytes by serializing self.
+    /// Variable length types should override it with something cheaper.
+    fn serialized_len(&self) -> usize {
+        match Self::FIXED_SIZE {
+            Some(size) => size,
+            None => {
+                let mut counter = ByteCounter(0);
+                let _ = self.serialize_into(&mut counter);
+                counter.0
+            }
+        }
+    }
 
-    fn serialize(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+    /// Serialized version of self extends the argument vector `v`,
+    /// which is then returned.
+    fn serialize_append(&self, mut v: Vec<u8>) -> Result<Vec<u8>, Error> {
+        self.serialize_into(&mut v)?;
+        Ok(v)
+    }
+
+    /// Serialized version of self is put into `buf`.
+    #[cfg(feature = "bytes")]
+    fn serialize_buf<B: bytes::BufMut + ?Sized>(&self, buf: &mut B) -> Result<(), Error> {
+        use bytes::BufMut;
+
+        self.serialize_into(&mut buf.writer())
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>, Error>
     where
         Self: Sized,
     {
-        // Generally the needed vector capacity should be equal to
-        // the number of bytes
-        let v = Vec::<u8>::with_capacity(size_of::<Self>());
+        let v = Vec::<u8>::with_capacity(self.serialized_len());
         self.serialize_append(v)
     }
 }
+
+/// Serializes a value of type `T` with an encoding chosen by the implementer
+/// instead of the one provided by `T`'s own [Serializable] impl.
+///
+/// Field attributes of `#[derive(Transmittable)]`, e.g., `#[transmittable(endian = "be")]`,
+/// expand to calls to this trait.
+pub trait SerializableAs<T: ?Sized> {
+    /// Same as [Serializable::FIXED_SIZE] but for the encoding of `T`.
+    const FIXED_SIZE: Option<usize> = None;
+
+    /// Same as [Serializable::serialize_into] but for a `value` of type `T`.
+    fn serialize_as_into<W: Write + ?Sized>(value: &T, w: &mut W) -> Result<(), Error>;
+
+    /// Same as [Serializable::serialized_len] but for a `value` of type `T`.
+    fn serialized_len_as(value: &T) -> usize {
+        match Self::FIXED_SIZE {
+            Some(size) => size,
+            None => {
+                let mut counter = ByteCounter(0);
+                let _ = Self::serialize_as_into(value, &mut counter);
+                counter.0
+            }
+        }
+    }
+}
+
+/// Returns the sum of `sizes` if all of them are fixed.
+///
+/// Used to compute [Serializable::FIXED_SIZE] of a struct from its fields.
+pub const fn fixed_size_sum(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut sum = 0;
+    let mut i = 0;
+
+    while i < sizes.len() {
+        match sizes[i] {
+            Some(size) => sum += size,
+            None => return None,
+        }
+        i += 1;
+    }
+
+    Some(sum)
+}
+
+/// Returns the size shared by all of `sizes` if they are fixed and equal.
+///
+/// Used to compute [Serializable::FIXED_SIZE] of an enum from its variants.
+pub const fn fixed_size_common(sizes: &[Option<usize>]) -> Option<usize> {
+    if sizes.is_empty() {
+        return None;
+    }
+
+    let mut i = 0;
+
+    while i < sizes.len() {
+        match (sizes[0], sizes[i]) {
+            (Some(first), Some(size)) if first == size => {}
+            _ => return None,
+        }
+        i += 1;
+    }
+
+    sizes[0]
+}
+
+/// [Write] implementation that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fixed_size_common, fixed_size_sum, Serializable};
+    use std::{io::Cursor, net::Ipv4Addr};
+
+    #[test]
+    fn test_fixed_size_sum() {
+        assert_eq!(fixed_size_sum(&[]), Some(0));
+        assert_eq!(fixed_size_sum(&[Some(16), Some(1), Some(4)]), Some(21));
+        assert_eq!(fixed_size_sum(&[Some(16), None]), None);
+    }
+
+    #[test]
+    fn test_fixed_size_common() {
+        assert_eq!(fixed_size_common(&[]), None);
+        assert_eq!(fixed_size_common(&[Some(2), Some(2)]), Some(2));
+        assert_eq!(fixed_size_common(&[Some(2), Some(3)]), None);
+        assert_eq!(fixed_size_common(&[Some(2), None]), None);
+    }
+
+    #[test]
+    fn test_serialized_len_fallback() {
+        // `&str` has no fixed size, the default implementation counts the bytes.
+        struct Name<'a>(&'a str);
+
+        impl Serializable for Name<'_> {
+            fn serialize_into<W: std::io::Write + ?Sized>(
+                &self,
+                w: &mut W,
+            ) -> Result<(), crate::transmittable::Error> {
+                self.0.serialize_into(w)
+            }
+        }
+
+        assert_eq!(Name("abc").serialized_len(), 4);
+    }
+
+    #[test]
+    fn test_serialize_into() {
+        let mut buf = [0_u8; 6];
+        let mut cursor = Cursor::new(&mut buf[..]);
+
+        if let Err(err) = 0x0201_u16.serialize_into(&mut cursor) {
+            panic!("{}", err);
+        }
+        if let Err(err) = Ipv4Addr::new(3, 4, 5, 6).serialize_into(&mut cursor) {
+            panic!("{}", err);
+        }
+
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_serialize_into_full_writer() {
+        let mut buf = [0_u8; 2];
+        let mut cursor = Cursor::new(&mut buf[..]);
+
+        match 7_u32.serialize_into(&mut cursor) {
+            Ok(()) => panic!("serialized 4 bytes into a 2 byte buffer"),
+            Err(err) => assert!(!err.is_eof()),
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_serialize_buf() {
+        let mut buf = bytes::BytesMut::new();
+
+        if let Err(err) = 0x0201_u16.serialize_buf(&mut buf) {
+            panic!("{}", err);
+        }
+
+        assert_eq!(&buf[..], [1, 2]);
+    }
+}
@@ -0,0 +1,118 @@
+use super::{
+    ByteContent, Deserializable, DeserializableAs, Error, FromByteContent, Serializable,
+    SerializableAs,
+};
+use std::io::Write;
+
+/// Transmits a byte or string field (`Vec<u8>`, [Bytes](super::Bytes), `&[u8]`, ...)
+/// as all the bytes remaining in the input, without any length or terminator.
+///
+/// Gnutella descriptors use this for trailers whose size is only known from the
+/// payload length, like the private vendor data of a QueryHit. It is the codec
+/// behind `#[transmittable(rest)]`, which has to be the last field.
+pub struct Rest;
+
+impl<T: ByteContent> SerializableAs<T> for Rest {
+    fn serialize_as_into<W: Write + ?Sized>(value: &T, w: &mut W) -> Result<(), Error> {
+        w.write_all(value.content())?;
+        Ok(())
+    }
+
+    fn serialized_len_as(value: &T) -> usize {
+        value.content().len()
+    }
+}
+
+impl<'de, T: FromByteContent<'de>> DeserializableAs<'de, T> for Rest {
+    fn deserialize_as(data: &'de [u8]) -> Result<(T, usize), Error> {
+        Ok((T::from_content(data)?, data.len()))
+    }
+}
+
+/// Transmits an `Option<T>` as the value if it is `Some` and as nothing at all
+/// if it is `None`.
+///
+/// Deserializes to `None` when the input is exhausted, so it only works for fields
+/// at the end of a descriptor, like the GGEP block of a Pong or Query. It is the
+/// codec behind `#[transmittable(optional)]`, which can only be followed by
+/// more optional fields.
+pub struct Optional;
+
+impl<T: Serializable> SerializableAs<Option<T>> for Optional {
+    fn serialize_as_into<W: Write + ?Sized>(value: &Option<T>, w: &mut W) -> Result<(), Error> {
+        match value {
+            Some(value) => value.serialize_into(w),
+            None => Ok(()),
+        }
+    }
+
+    fn serialized_len_as(value: &Option<T>) -> usize {
+        value.as_ref().map_or(0, Serializable::serialized_len)
+    }
+}
+
+impl<'de, T: Deserializable<'de>> DeserializableAs<'de, Option<T>> for Optional {
+    fn deserialize_as(data: &'de [u8]) -> Result<(Option<T>, usize), Error> {
+        if data.is_empty() {
+            return Ok((None, 0));
+        }
+
+        let (value, bytes_parsed) = T::deserialize(data)?;
+        Ok((Some(value), bytes_parsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Optional, Rest};
+    use crate::transmittable::{DeserializableAs, SerializableAs};
+
+    #[test]
+    fn test_rest_transmittable() {
+        let data = [0xc3, 0x82, 0x00];
+
+        match <Rest as DeserializableAs<Vec<u8>>>::deserialize_as(&data) {
+            Ok((rest, bytes_parsed)) => {
+                assert_eq!(rest, data);
+                assert_eq!(bytes_parsed, 3);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        let mut serialized = Vec::new();
+
+        if let Err(err) = Rest::serialize_as_into(&data.to_vec(), &mut serialized) {
+            panic!("{}", err);
+        }
+
+        assert_eq!(serialized, data);
+    }
+
+    #[test]
+    fn test_optional_transmittable() {
+        match <Optional as DeserializableAs<Option<u16>>>::deserialize_as(&[]) {
+            Ok((value, bytes_parsed)) => {
+                assert_eq!(value, None);
+                assert_eq!(bytes_parsed, 0);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        match <Optional as DeserializableAs<Option<u16>>>::deserialize_as(&[1, 2]) {
+            Ok((value, bytes_parsed)) => {
+                assert_eq!(value, Some(0x0201));
+                assert_eq!(bytes_parsed, 2);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        // A truncated value is an error rather than `None`.
+        match <Optional as DeserializableAs<Option<u16>>>::deserialize_as(&[1]) {
+            Err(err) => assert!(err.is_eof()),
+            res => panic!("unexpected result {:?}", res),
+        }
+
+        assert_eq!(Optional::serialized_len_as(&None::<u16>), 0);
+        assert_eq!(Optional::serialized_len_as(&Some(1_u16)), 2);
+    }
+}
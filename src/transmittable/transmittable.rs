@@ -1,3 +1,7 @@
-use super::{Deserializable, Serializable};
+use super::{DeserializableOwned, Serializable};
 
-pub trait Transmittable: Serializable + Deserializable {}
+/// Types that can be both serialized and deserialized without borrowing from the input.
+///
+/// Types borrowing from the input only implement [Serializable] and
+/// [Deserializable](super::Deserializable).
+pub trait Transmittable: Serializable + DeserializableOwned {}
@@ -3,5 +3,21 @@ fn test_transmittable_derive() {
     let t = trybuild::TestCases::new();
 
     t.compile_fail("tests/trybuild_transmittable_derive/fields_transmittable_impl.rs");
+    t.compile_fail("tests/trybuild_transmittable_derive/invalid_endian_attribute.rs");
+    t.compile_fail("tests/trybuild_transmittable_derive/enum_missing_tag.rs");
+    t.compile_fail("tests/trybuild_transmittable_derive/invalid_encoding_attribute.rs");
+    t.compile_fail("tests/trybuild_transmittable_derive/invalid_count_attribute.rs");
+    t.compile_fail("tests/trybuild_transmittable_derive/invalid_trailing_attribute.rs");
     t.pass("tests/trybuild_transmittable_derive/valid_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/endian_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/enum_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/error_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/borrowed_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/serialized_len_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/strings_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/count_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/trailing_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/std_types_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/validate_transmittable_derive.rs");
+    t.pass("tests/trybuild_transmittable_derive/flags_transmittable_derive.rs");
 }
@@ -0,0 +1,49 @@
+use gnutella::{
+    transmittable::{Deserializable, Serializable},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Query<'a> {
+    min_speed: u16,
+    search_criteria: &'a str,
+    extensions: &'a [u8],
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Wrapper<'de, 'b, T> {
+    value: T,
+    query: Query<'de>,
+    name: &'b str,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let data = b"\x00\x00metallica\0\xc3\x82\x00";
+
+    let (query, bytes_parsed) = <Query as Deserializable>::deserialize(data)?;
+
+    assert_eq!(bytes_parsed, data.len());
+    assert_eq!(
+        query,
+        Query {
+            min_speed: 0,
+            search_criteria: "metallica",
+            extensions: &[0xc3, 0x82, 0x00],
+        }
+    );
+
+    // The deserialized query borrows from `data`.
+    assert_eq!(query.search_criteria.as_ptr(), data[2..].as_ptr());
+
+    assert_eq!(query.serialize()?, data);
+
+    let wrapper = Wrapper {
+        value: 7_u8,
+        query,
+        name: "ignored",
+    };
+
+    assert_eq!(wrapper.serialize()?[0], 7);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use gnutella::{
+    transmittable::{Deserializable, Error, Serializable},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Result {
+    file_index: u32,
+    file_size: u32,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct QueryHit {
+    number_of_hits: u8,
+    #[transmittable(endian = "be")]
+    port: u16,
+    #[transmittable(count = "number_of_hits")]
+    results: Vec<Result>,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u8)]
+enum Patch {
+    #[transmittable(tag = 1)]
+    Entries(u16, #[transmittable(count = "0")] Vec<u8>),
+}
+
+fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let query_hit = QueryHit {
+        number_of_hits: 2,
+        port: 6346,
+        results: vec![
+            Result {
+                file_index: 1,
+                file_size: 2,
+            },
+            Result {
+                file_index: 3,
+                file_size: 4,
+            },
+        ],
+    };
+
+    let data = query_hit.serialize()?;
+
+    assert_eq!(data.len(), 1 + 2 + 2 * 8);
+    assert_eq!(query_hit.serialized_len(), data.len());
+
+    let (deserialized, bytes_parsed) = QueryHit::deserialize(&data)?;
+
+    assert_eq!(bytes_parsed, data.len());
+    assert_eq!(deserialized, query_hit);
+
+    // The count has to agree with the items.
+    let mismatched = QueryHit {
+        number_of_hits: 3,
+        ..query_hit
+    };
+
+    match mismatched.serialize() {
+        Err(Error::SerializationFailed { field, .. }) => assert_eq!(field.as_str(), "results"),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    // Too few items in the input.
+    match QueryHit::deserialize(&data[..data.len() - 1]) {
+        Err(err) => {
+            assert!(err.is_eof());
+            assert_eq!(err.field().as_str(), "results.1.file_size");
+            assert_eq!(err.offset(), Some(15));
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let patch = Patch::Entries(3, vec![7, 8, 9]);
+    let data = patch.serialize()?;
+
+    assert_eq!(data, [1, 3, 0, 7, 8, 9]);
+    assert_eq!(Patch::deserialize(&data)?, (patch, data.len()));
+
+    Ok(())
+}
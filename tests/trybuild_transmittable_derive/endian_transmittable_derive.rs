@@ -0,0 +1,41 @@
+use gnutella::{
+    transmittable::{BigEndian, Deserializable, Serializable},
+    Transmittable,
+};
+use std::net::Ipv4Addr;
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Test {
+    #[transmittable(endian = "le")]
+    port: u16,
+    #[transmittable(endian = "be")]
+    ip: Ipv4Addr,
+    #[transmittable(endian = "be")]
+    value: u32,
+    wrapped: BigEndian<u16>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let instance = Test {
+        port: 6346,
+        ip: Ipv4Addr::new(1, 2, 3, 4),
+        value: 0x0a0b0c0d,
+        wrapped: BigEndian(0x0102),
+    };
+
+    let serialized_instance = instance.serialize()?;
+
+    assert_eq!(
+        serialized_instance,
+        [0xca, 0x18, 1, 2, 3, 4, 0x0a, 0x0b, 0x0c, 0x0d, 1, 2]
+    );
+
+    let (deserialized_instance, bytes_parsed) =
+        <Test as Deserializable>::deserialize(&serialized_instance)?;
+
+    assert_eq!(bytes_parsed, 12);
+
+    assert_eq!(instance, deserialized_instance);
+
+    Ok(())
+}
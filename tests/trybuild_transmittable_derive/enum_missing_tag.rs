@@ -0,0 +1,11 @@
+use gnutella::Transmittable;
+
+#[derive(Transmittable)]
+#[transmittable(tag = u8)]
+enum Payload {
+    #[transmittable(tag = 0x00)]
+    Ping,
+    Pong(u16),
+}
+
+fn main() {}
@@ -0,0 +1,71 @@
+use gnutella::{
+    transmittable::{Deserializable, Serializable},
+    Transmittable,
+};
+use std::net::Ipv4Addr;
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u8)]
+enum Payload {
+    #[transmittable(tag = 0x00)]
+    Ping,
+    #[transmittable(tag = 0x01)]
+    Pong {
+        port: u16,
+        #[transmittable(endian = "be")]
+        ip: Ipv4Addr,
+    },
+    #[transmittable(tag = 0x80)]
+    Query(u16, u8),
+    // Field names that are also used by the generated code.
+    #[transmittable(tag = 0x81)]
+    Data { data: u8, v: u8, start: u8 },
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u16)]
+enum Discriminant {
+    A = 1,
+    B = 0x0200,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let payloads = [
+        (Payload::Ping, vec![0x00]),
+        (
+            Payload::Pong {
+                port: 6346,
+                ip: Ipv4Addr::new(1, 2, 3, 4),
+            },
+            vec![0x01, 0xca, 0x18, 1, 2, 3, 4],
+        ),
+        (Payload::Query(0x0102, 3), vec![0x80, 2, 1, 3]),
+        (
+            Payload::Data {
+                data: 1,
+                v: 2,
+                start: 3,
+            },
+            vec![0x81, 1, 2, 3],
+        ),
+    ];
+
+    for (payload, bytes) in payloads.iter() {
+        assert_eq!(&payload.serialize()?, bytes);
+
+        let (deserialized_payload, bytes_parsed) = <Payload as Deserializable>::deserialize(bytes)?;
+
+        assert_eq!(bytes_parsed, bytes.len());
+        assert_eq!(payload, &deserialized_payload);
+    }
+
+    assert!(<Payload as Deserializable>::deserialize(&[0x02]).is_err());
+
+    assert_eq!(Discriminant::B.serialize()?, [0x00, 0x02]);
+    assert_eq!(
+        <Discriminant as Deserializable>::deserialize(&[0x01, 0x00])?,
+        (Discriminant::A, 2)
+    );
+
+    Ok(())
+}
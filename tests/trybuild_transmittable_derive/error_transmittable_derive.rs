@@ -0,0 +1,55 @@
+use gnutella::{
+    transmittable::{Deserializable, Error},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Header {
+    ttl: u8,
+    length: u32,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Message {
+    id: u16,
+    header: Header,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u8)]
+enum Payload {
+    #[transmittable(tag = 0x01)]
+    Pong { port: u16 },
+}
+
+fn main() {
+    match <Message as Deserializable>::deserialize(&[1, 0, 7, 1, 0]) {
+        Err(Error::UnexpectedEof {
+            field,
+            needed,
+            available,
+            offset,
+        }) => {
+            assert_eq!(field.as_str(), "header.length");
+            assert_eq!((needed, available, offset), (4, 2, 3));
+        }
+        res => panic!("expected UnexpectedEof, got {:?}", res),
+    }
+
+    match <Payload as Deserializable>::deserialize(&[0x01, 0x02]) {
+        Err(err) => {
+            assert!(err.is_eof());
+            assert_eq!(err.field().as_str(), "Pong.port");
+            assert_eq!(err.offset(), Some(1));
+        }
+        res => panic!("expected an error, got {:?}", res),
+    }
+
+    match <Payload as Deserializable>::deserialize(&[0x02]) {
+        Err(err @ Error::InvalidValue { .. }) => {
+            assert_eq!(err.field().as_str(), "tag");
+            assert_eq!(err.offset(), Some(0));
+        }
+        res => panic!("expected InvalidValue, got {:?}", res),
+    }
+}
@@ -1,7 +1,4 @@
-use gnutella::{
-    transmittable::{Deserializable, Serializable, Transmittable},
-    Transmittable,
-};
+use gnutella::Transmittable;
 
 #[derive(Transmittable)]
 struct Test {
@@ -10,7 +7,7 @@ struct Test {
 }
 
 fn main() {
-    let instance = Test {
+    let _ = Test {
         x: 3,
         y: "hello".into(),
     };
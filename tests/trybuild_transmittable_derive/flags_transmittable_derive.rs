@@ -0,0 +1,62 @@
+use gnutella::{
+    transmittable::{Deserializable, Serializable},
+    Transmittable, TransmittableFlags,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, TransmittableFlags)]
+#[transmittable(endian = "be")]
+#[transmittable(flags(
+    FLAGS_PRESENT = 1 << 15,
+    FIREWALLED = 1 << 14,
+    XML = 1 << 13,
+    LEAF_GUIDED = 1 << 12,
+    GGEP_H = 1 << 11,
+    OOB = 1 << 10,
+))]
+struct QueryFlags(u16);
+
+#[derive(Clone, Copy, PartialEq, Eq, TransmittableFlags)]
+#[transmittable(flags(PUSH = 0x01, BUSY = 0x04))]
+struct QhdFlags(u8);
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Query {
+    flags: QueryFlags,
+    control: QhdFlags,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut flags = QueryFlags::FLAGS_PRESENT | QueryFlags::XML;
+    flags.set(QueryFlags::OOB, true);
+    flags.remove(QueryFlags::XML);
+
+    assert!(flags.contains(QueryFlags::FLAGS_PRESENT | QueryFlags::OOB));
+    assert!(!flags.intersects(QueryFlags::FIREWALLED | QueryFlags::XML));
+    assert_eq!(flags.bits(), 0x8400);
+    assert_eq!(flags.serialize()?, [0x84, 0x00]);
+    assert_eq!(format!("{:?}", flags), "QueryFlags(FLAGS_PRESENT | OOB)");
+
+    // Bits we don't know about survive a round trip.
+    let (flags, bytes_parsed) = QueryFlags::deserialize(&[0xc0, 0x05])?;
+
+    assert_eq!(bytes_parsed, 2);
+    assert_eq!(flags.unknown_bits(), 0x0005);
+    assert_eq!(flags.serialize()?, [0xc0, 0x05]);
+    assert_eq!(
+        format!("{:?}", flags),
+        "QueryFlags(FLAGS_PRESENT | FIREWALLED | 0x5)"
+    );
+
+    let query = Query {
+        flags: QueryFlags::from_bits_retain(0x8000),
+        control: QhdFlags::all(),
+    };
+
+    let data = query.serialize()?;
+
+    assert_eq!(data, [0x80, 0x00, 0x05]);
+    assert_eq!(Query::deserialize(&data)?, (query, 3));
+    assert!(QhdFlags::empty().is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use gnutella::Transmittable;
+
+#[derive(Transmittable)]
+struct Hits {
+    #[transmittable(count = "number_of_hits")]
+    results: Vec<u32>,
+    number_of_hits: u8,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use gnutella::Transmittable;
+
+#[derive(Transmittable)]
+struct Name {
+    #[transmittable(encoding = "len_u64")]
+    name: String,
+}
+
+#[derive(Transmittable)]
+struct Limited {
+    #[transmittable(max_len = 16)]
+    name: Vec<u8>,
+}
+
+fn main() {}
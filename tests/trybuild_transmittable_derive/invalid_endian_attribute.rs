@@ -0,0 +1,9 @@
+use gnutella::Transmittable;
+
+#[derive(Transmittable)]
+struct Test {
+    #[transmittable(endian = "middle")]
+    x: u32,
+}
+
+fn main() {}
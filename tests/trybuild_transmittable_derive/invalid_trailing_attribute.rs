@@ -0,0 +1,17 @@
+use gnutella::Transmittable;
+
+#[derive(Transmittable)]
+struct Rest {
+    #[transmittable(rest)]
+    data: Vec<u8>,
+    ttl: u8,
+}
+
+#[derive(Transmittable)]
+struct Optional {
+    #[transmittable(optional)]
+    shared_files: Option<u32>,
+    ttl: u8,
+}
+
+fn main() {}
@@ -0,0 +1,64 @@
+use gnutella::{transmittable::Serializable, Transmittable};
+use std::net::Ipv4Addr;
+use uuid::Uuid;
+
+#[derive(Transmittable)]
+struct Header {
+    id: Uuid,
+    payload_type: u8,
+    ttl: u8,
+    hops: u8,
+    #[transmittable(endian = "le")]
+    payload_length: u32,
+}
+
+#[derive(Transmittable)]
+struct Query<'a> {
+    min_speed: u16,
+    search_criteria: &'a str,
+}
+
+#[derive(Transmittable)]
+struct Unit;
+
+#[derive(Transmittable)]
+#[transmittable(tag = u8)]
+enum SameSize {
+    #[transmittable(tag = 0)]
+    A(u16),
+    #[transmittable(tag = 1)]
+    B { x: u8, y: u8 },
+}
+
+#[derive(Transmittable)]
+#[transmittable(tag = u8)]
+enum DifferentSize {
+    #[transmittable(tag = 0)]
+    A,
+    #[transmittable(tag = 1)]
+    B(Ipv4Addr),
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(Header::FIXED_SIZE, Some(23));
+    assert_eq!(Query::FIXED_SIZE, None);
+    assert_eq!(Unit::FIXED_SIZE, Some(0));
+    assert_eq!(SameSize::FIXED_SIZE, Some(3));
+    assert_eq!(DifferentSize::FIXED_SIZE, None);
+
+    let query = Query {
+        min_speed: 0,
+        search_criteria: "metallica",
+    };
+
+    assert_eq!(query.serialized_len(), 12);
+    assert_eq!(query.serialized_len(), query.serialize()?.len());
+
+    assert_eq!(DifferentSize::A.serialized_len(), 1);
+    assert_eq!(DifferentSize::B(Ipv4Addr::LOCALHOST).serialized_len(), 5);
+
+    // serialize() preallocates exactly.
+    assert_eq!(query.serialize()?.capacity(), 12);
+
+    Ok(())
+}
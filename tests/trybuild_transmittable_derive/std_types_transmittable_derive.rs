@@ -0,0 +1,69 @@
+use gnutella::{
+    transmittable::{Deserializable, Serializable},
+    Transmittable,
+};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Pong {
+    #[transmittable(port_first)]
+    addr: SocketAddrV4,
+    counts: (u32, u32),
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Push {
+    servent_id: [u8; 16],
+    file_index: u32,
+    addr: SocketAddrV4,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Extension {
+    vendor_code: [u8; 4],
+    firewalled: bool,
+    addr: SocketAddrV6,
+    #[transmittable(endian = "le")]
+    reversed: Ipv6Addr,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 6346);
+
+    let pong = Pong {
+        addr,
+        counts: (10, 20),
+    };
+
+    let data = pong.serialize()?;
+
+    assert_eq!(data[..6], [0xca, 0x18, 1, 2, 3, 4]);
+    assert_eq!(Pong::deserialize(&data)?, (pong, 14));
+    assert_eq!(<Pong as Serializable>::FIXED_SIZE, Some(14));
+
+    let push = Push {
+        servent_id: [7; 16],
+        file_index: 1,
+        addr,
+    };
+
+    let data = push.serialize()?;
+
+    assert_eq!(data[20..], [1, 2, 3, 4, 0xca, 0x18]);
+    assert_eq!(Push::deserialize(&data)?, (push, 26));
+
+    let extension = Extension {
+        vendor_code: *b"LIME",
+        firewalled: true,
+        addr: SocketAddrV6::new(Ipv6Addr::LOCALHOST, 6346, 0, 0),
+        reversed: Ipv6Addr::LOCALHOST,
+    };
+
+    let data = extension.serialize()?;
+
+    assert_eq!(data[..5], *b"LIME\x01");
+    assert_eq!(data[23], 1);
+    assert_eq!(Extension::deserialize(&data)?, (extension, 39));
+
+    Ok(())
+}
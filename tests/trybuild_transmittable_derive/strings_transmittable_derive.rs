@@ -0,0 +1,47 @@
+use gnutella::{
+    transmittable::{Deserializable, Error, Serializable},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Strings<'a> {
+    #[transmittable(encoding = "nul")]
+    name: String,
+    #[transmittable(encoding = "len_u16", endian = "be")]
+    vendor: String,
+    #[transmittable(encoding = "len_u8", max_len = 4)]
+    code: Vec<u8>,
+    #[transmittable(encoding = "nul", max_len = 8)]
+    borrowed: &'a str,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let strings = Strings {
+        name: "file".to_string(),
+        vendor: "LIME".to_string(),
+        code: vec![1, 2],
+        borrowed: "urn",
+    };
+
+    let data = strings.serialize()?;
+
+    assert_eq!(data, b"file\0\x00\x04LIME\x02\x01\x02urn\0");
+    assert_eq!(strings.serialized_len(), data.len());
+
+    let (deserialized, bytes_parsed) = <Strings as Deserializable>::deserialize(&data)?;
+
+    assert_eq!(bytes_parsed, data.len());
+    assert_eq!(deserialized, strings);
+
+    let too_long = Strings {
+        code: vec![0; 5],
+        ..strings
+    };
+
+    match too_long.serialize() {
+        Err(Error::SerializationFailed { field, .. }) => assert_eq!(field.as_str(), "code"),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    Ok(())
+}
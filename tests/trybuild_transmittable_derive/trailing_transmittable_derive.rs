@@ -0,0 +1,81 @@
+use gnutella::{
+    transmittable::{Bytes, Deserializable, Error, Serializable},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Pong {
+    #[transmittable(endian = "be")]
+    port: u16,
+    #[transmittable(optional)]
+    shared_files: Option<u32>,
+    #[transmittable(optional)]
+    shared_kbytes: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct QueryHit {
+    number_of_hits: u8,
+    #[transmittable(rest)]
+    private_data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+struct Push<'a>(u32, #[transmittable(rest)] &'a [u8]);
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u8)]
+enum Extension {
+    #[transmittable(tag = 0xc3)]
+    Ggep(#[transmittable(rest)] Bytes),
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let pong = Pong {
+        port: 6346,
+        shared_files: Some(1),
+        shared_kbytes: None,
+    };
+
+    let data = pong.serialize()?;
+
+    assert_eq!(data, [0x18, 0xca, 1, 0, 0, 0]);
+    assert_eq!(Pong::deserialize(&data)?, (pong, 6));
+
+    let (pong, bytes_parsed) = Pong::deserialize(&data[..2])?;
+
+    assert_eq!(bytes_parsed, 2);
+    assert_eq!(pong.shared_files, None);
+
+    // A present field after an absent one couldn't be told apart from it.
+    let pong = Pong {
+        shared_kbytes: Some(2),
+        ..pong
+    };
+
+    match pong.serialize() {
+        Err(Error::SerializationFailed { field, .. }) => assert_eq!(field.as_str(), "shared_kbytes"),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let query_hit = QueryHit {
+        number_of_hits: 0,
+        private_data: vec![1, 2, 3],
+    };
+
+    let data = query_hit.serialize()?;
+
+    assert_eq!(data, [0, 1, 2, 3]);
+    assert_eq!(query_hit.serialized_len(), 4);
+    assert_eq!(QueryHit::deserialize(&data)?, (query_hit, 4));
+
+    let (push, _) = Push::deserialize(&data)?;
+
+    assert_eq!(push, Push(0x0302_0100, &[]));
+
+    let extension = Extension::Ggep(Bytes(vec![0x82, 0x00]));
+
+    assert_eq!(Extension::deserialize(&[0xc3, 0x82, 0x00])?, (extension, 3));
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use gnutella::{
+    transmittable::{Deserializable, Error, Serializable},
+    Transmittable,
+};
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(validate = "check_hops")]
+struct Header {
+    #[transmittable(range = "1..=7")]
+    ttl: u8,
+    hops: u8,
+    #[transmittable(validate = "nonzero_port", endian = "be")]
+    port: u16,
+}
+
+fn check_hops(header: &Header) -> Result<(), String> {
+    if header.ttl + header.hops > 7 {
+        return Err(format!("ttl + hops is {}", header.ttl + header.hops));
+    }
+    Ok(())
+}
+
+fn nonzero_port(port: &u16) -> Result<(), &'static str> {
+    if *port == 0 {
+        return Err("port is 0");
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Transmittable)]
+#[transmittable(tag = u8, validate = "self::checks::known")]
+enum Payload {
+    #[transmittable(tag = 0)]
+    Ping,
+    #[transmittable(tag = 1)]
+    Bye(#[transmittable(range = 200..600)] u16),
+}
+
+mod checks {
+    pub fn known(payload: &super::Payload) -> Result<(), String> {
+        match payload {
+            super::Payload::Ping => Err("ping is not expected".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let header = Header {
+        ttl: 3,
+        hops: 2,
+        port: 6346,
+    };
+
+    let data = header.serialize()?;
+
+    assert_eq!(Header::deserialize(&data)?, (header, 4));
+
+    match Header::deserialize(&[0, 0, 0x18, 0xca]) {
+        Err(Error::InvalidValue { field, offset, .. }) => {
+            assert_eq!(field.as_str(), "ttl");
+            assert_eq!(offset, 0);
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    match Header::deserialize(&[1, 0, 0, 0]) {
+        Err(Error::InvalidValue { field, offset, reason }) => {
+            assert_eq!(field.as_str(), "port");
+            assert_eq!(offset, 2);
+            assert_eq!(reason, "port is 0");
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    // The container check runs on the whole value.
+    match Header::deserialize(&[5, 3, 0x18, 0xca]) {
+        Err(Error::InvalidValue { field, reason, .. }) => {
+            assert!(field.is_empty());
+            assert_eq!(reason, "ttl + hops is 8");
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let header = Header {
+        ttl: 0,
+        hops: 0,
+        port: 6346,
+    };
+
+    match header.serialize() {
+        Err(Error::SerializationFailed { field, .. }) => assert_eq!(field.as_str(), "ttl"),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    assert_eq!(Payload::deserialize(&[1, 0x2c, 0x01])?, (Payload::Bye(300), 3));
+
+    match Payload::deserialize(&[1, 0, 0]) {
+        Err(Error::InvalidValue { field, offset, .. }) => {
+            assert_eq!(field.as_str(), "Bye.0");
+            assert_eq!(offset, 1);
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    assert!(Payload::deserialize(&[0]).is_err());
+    assert!(Payload::Ping.serialize().is_err());
+
+    Ok(())
+}